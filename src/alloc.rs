@@ -0,0 +1,242 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The system allocator, counting allocations and live bytes as it goes.
+///
+/// Install it with `#[global_allocator]` to measure a whole program: every allocation pays for a
+/// few atomic operations, which is why this crate only does so with the `alloc-stats` feature.
+pub struct CountingAllocator {
+    allocations: AtomicUsize,
+    allocated: AtomicUsize,
+    live: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+// Built with the `alloc-stats` feature, the whole program allocates through a counting allocator.
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator::new();
+
+/// Counters of a [`CountingAllocator`], or the difference between two snapshots of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AllocStats {
+    /// Number of allocations, reallocations included.
+    pub allocations: usize,
+    /// Bytes requested by all allocations. A reallocation counts for the bytes it adds.
+    pub allocated: usize,
+    /// Highest number of bytes allocated at once.
+    pub peak: usize,
+}
+
+impl CountingAllocator {
+    pub const fn new() -> Self {
+        Self {
+            allocations: AtomicUsize::new(0),
+            allocated: AtomicUsize::new(0),
+            live: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> AllocStats {
+        AllocStats {
+            allocations: self.allocations.load(Ordering::Relaxed),
+            allocated: self.allocated.load(Ordering::Relaxed),
+            peak: self.peak.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Start measuring the peak again from the bytes currently live, so that it covers what
+    /// happens next only.
+    pub fn reset_peak(&self) {
+        self.peak.store(self.live.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    fn grow(&self, bytes: usize) {
+        self.allocated.fetch_add(bytes, Ordering::Relaxed);
+        let live = self.live.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.peak.fetch_max(live, Ordering::Relaxed);
+    }
+
+    fn shrink(&self, bytes: usize) {
+        self.live.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+impl Default for CountingAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            if new_size > layout.size() {
+                self.grow(new_size - layout.size());
+            } else {
+                self.shrink(layout.size() - new_size);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// Run `f` and return what it allocated along with its result, for reporting per run. Always
+/// `None` unless built with the `alloc-stats` feature.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocStats>) {
+    #[cfg(feature = "alloc-stats")]
+    {
+        GLOBAL.reset_peak();
+        let before = GLOBAL.stats();
+        let value = f();
+        (value, Some(GLOBAL.stats().since(&before)))
+    }
+    #[cfg(not(feature = "alloc-stats"))]
+    (f(), None)
+}
+
+impl AllocStats {
+    /// What happened between `earlier` and this snapshot. The peak is kept as is, see
+    /// [`CountingAllocator::reset_peak`].
+    pub fn since(&self, earlier: &AllocStats) -> AllocStats {
+        AllocStats {
+            allocations: self.allocations - earlier.allocations,
+            allocated: self.allocated - earlier.allocated,
+            peak: self.peak,
+        }
+    }
+}
+
+// Exact counts, in bytes, so that `run-all` can read them back from the stderr of its solutions.
+impl Display for AllocStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "allocations:{} allocated:{} peak:{}",
+            self.allocations, self.allocated, self.peak
+        )
+    }
+}
+
+impl FromStr for AllocStats {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split_whitespace().map(|field| field.split_once(':'));
+        let mut next = |name: &str| match fields.next() {
+            Some(Some((key, value))) if key == name => value.parse().map_err(|_| ()),
+            _ => Err(()),
+        };
+        let stats = AllocStats {
+            allocations: next("allocations")?,
+            allocated: next("allocated")?,
+            peak: next("peak")?,
+        };
+        match fields.next() {
+            None => Ok(stats),
+            Some(_) => Err(()),
+        }
+    }
+}
+
+/// A number of bytes in the largest binary unit that keeps it above 1, e.g. `1.5KiB`.
+pub fn format_bytes(n: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = n as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{n}B"),
+        _ => format!("{value:.1}{}", UNITS[unit]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_allocations_and_peak() {
+        let allocator = CountingAllocator::new();
+        let small = Layout::from_size_align(16, 8).unwrap();
+        unsafe {
+            let a = allocator.alloc(small);
+            let b = allocator.realloc(allocator.alloc(small), small, 64);
+            allocator.dealloc(a, small);
+            allocator.reset_peak();
+            allocator.dealloc(b, Layout::from_size_align(64, 8).unwrap());
+        }
+        assert_eq!(
+            allocator.stats(),
+            AllocStats {
+                allocations: 3,
+                allocated: 16 + 16 + 48,
+                peak: 64,
+            }
+        );
+        assert_eq!(allocator.live.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn measures_allocations_with_the_feature_only() {
+        let (value, stats) = measure(|| vec![0u8; 4096].len());
+        assert_eq!(value, 4096);
+        assert_eq!(stats.is_some(), cfg!(feature = "alloc-stats"));
+        if let Some(stats) = stats {
+            assert!(stats.allocations >= 1 && stats.peak >= 4096, "{stats}");
+        }
+    }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(1536), "1.5KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0MiB");
+    }
+
+    #[test]
+    fn parses_what_it_prints() {
+        let stats = AllocStats {
+            allocations: 3,
+            allocated: 1536,
+            peak: 1024,
+        };
+        assert_eq!(stats.to_string(), "allocations:3 allocated:1536 peak:1024");
+        assert_eq!(stats.to_string().parse(), Ok(stats));
+        assert!("allocations:3 allocated:1.5KiB peak:1024"
+            .parse::<AllocStats>()
+            .is_err());
+        assert!("allocations:3 allocated:1536".parse::<AllocStats>().is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::render::{Image, Rgb};
+
+mod terminal;
+
+use terminal::Player;
+
+/// Command line options shared by the days that can record their simulation.
+#[derive(Debug, Clone, clap::Args)]
+pub struct AnimationArgs {
+    /// Record the simulation as an animated GIF in this file.
+    #[arg(long, global = true)]
+    pub gif: Option<PathBuf>,
+
+    /// Only keep one frame every this many simulation steps.
+    #[arg(long, global = true, default_value_t = 1)]
+    pub gif_stride: usize,
+
+    /// Pixels per cell side. Defaults to a scale that makes the animation about 512 pixels wide.
+    #[arg(long, global = true)]
+    pub gif_scale: Option<usize>,
+
+    /// Time each frame is shown, in milliseconds.
+    #[arg(long, global = true, default_value_t = 50)]
+    pub gif_delay: u16,
+
+    /// Play the simulation in the terminal, see the keys shown below the animation.
+    #[arg(long, global = true)]
+    pub animate: bool,
+
+    /// Frames per second when playing the simulation in the terminal.
+    #[arg(long, global = true, default_value_t = 10)]
+    pub fps: u32,
+}
+
+impl AnimationArgs {
+    /// A recorder for the options given, which does nothing unless `--gif` or `--animate` is set.
+    pub fn recorder(&self) -> Result<Recorder> {
+        let player = match self.animate {
+            true => Some(Player::start(self.fps)?),
+            false => None,
+        };
+        Ok(Recorder {
+            path: self.gif.clone(),
+            stride: self.gif_stride.max(1),
+            scale: self.gif_scale,
+            // GIF delays are in hundredths of a second
+            delay: self.gif_delay.div_ceil(10),
+            steps: 0,
+            frames: 0,
+            encoder: None,
+            player,
+            error: None,
+        })
+    }
+}
+
+/// Collects the frames of a simulation into an animated GIF, or plays them in the terminal.
+///
+/// Simulations call [`Recorder::record`] once per step, with a closure drawing the current state.
+/// The closure only runs for the steps that end up in the animation, so recording is free when
+/// disabled. Recording errors don't interrupt the simulation: the first one is kept and returned
+/// by [`Recorder::finish`].
+pub struct Recorder {
+    path: Option<PathBuf>,
+    stride: usize,
+    scale: Option<usize>,
+    delay: u16,
+    steps: usize,
+    frames: usize,
+    encoder: Option<gif::Encoder<BufWriter<File>>>,
+    player: Option<Player>,
+    error: Option<anyhow::Error>,
+}
+
+impl Recorder {
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some() || self.player.is_some()
+    }
+
+    /// Record one step of the simulation.
+    pub fn record(&mut self, frame: impl FnOnce() -> Image) {
+        if !self.is_enabled() || self.error.is_some() {
+            return;
+        }
+        self.steps += 1;
+        if (self.steps - 1).is_multiple_of(self.stride) {
+            let image = frame();
+            if self.path.is_some() {
+                self.write(&image);
+            }
+            self.show(&image);
+        }
+    }
+
+    /// Record the final state and complete the file. In the terminal, the final state stays on
+    /// screen until a key is pressed.
+    pub fn finish(mut self, last: impl FnOnce() -> Image) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let image = last();
+        if let Some(player) = self.player.as_mut() {
+            if self.error.is_none() {
+                player.end(&image)?;
+            }
+        }
+        // leave the terminal before reporting anything
+        self.player = None;
+        if self.path.is_some() {
+            self.write(&image);
+        }
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let (Some(path), Some(encoder)) = (self.path, self.encoder) else {
+            return Ok(());
+        };
+        let mut out = encoder
+            .into_inner()
+            .with_context(|| format!("writing '{}'", path.display()))?;
+        out.flush()?;
+        tracing::info!(
+            "recorded {} frames of {} steps to {}",
+            self.frames,
+            self.steps,
+            path.display()
+        );
+        Ok(())
+    }
+
+    fn write(&mut self, image: &Image) {
+        if let Err(err) = self.try_write(image) {
+            self.error = Some(err);
+        }
+    }
+
+    // Stop playing once the user quits the animation, the simulation carries on.
+    fn show(&mut self, image: &Image) {
+        let Some(player) = self.player.as_mut() else {
+            return;
+        };
+        match player.show(image, self.steps - 1) {
+            Ok(true) => {}
+            Ok(false) => self.player = None,
+            Err(err) => {
+                self.player = None;
+                self.error = Some(err);
+            }
+        }
+    }
+
+    fn try_write(&mut self, image: &Image) -> Result<()> {
+        let path = self.path.as_ref().unwrap();
+        let scale = self.scale.unwrap_or_else(|| image.default_scale()).max(1);
+        let (width, height) = (dimension(image.width() * scale)?, dimension(image.height() * scale)?);
+        let encoder = match self.encoder.as_mut() {
+            Some(encoder) => encoder,
+            None => {
+                let file = File::create(path).with_context(|| format!("creating '{}'", path.display()))?;
+                let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &[])?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                self.encoder.insert(encoder)
+            }
+        };
+
+        let pixels = image.pixels(scale);
+        let mut frame = match indexed(&pixels) {
+            Some((indices, palette)) => gif::Frame::from_palette_pixels(width, height, indices, palette, None),
+            None => gif::Frame::from_rgb_speed(width, height, &pixels, 10),
+        };
+        frame.delay = self.delay;
+        encoder
+            .write_frame(&frame)
+            .with_context(|| format!("writing '{}'", path.display()))?;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+fn dimension(pixels: usize) -> Result<u16> {
+    u16::try_from(pixels).map_err(|_| anyhow!("animation too large ({pixels} pixels), use a smaller --gif-scale"))
+}
+
+// Grids use a handful of colours, so frames can usually be encoded exactly without quantization.
+fn indexed(pixels: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut palette: HashMap<Rgb, u8> = HashMap::new();
+    let mut indices = Vec::with_capacity(pixels.len() / 3);
+    for rgb in pixels.chunks_exact(3) {
+        let rgb = [rgb[0], rgb[1], rgb[2]];
+        let next = palette.len();
+        let index = match palette.get(&rgb) {
+            Some(index) => *index,
+            None if next < 256 => *palette.entry(rgb).or_insert(next as u8),
+            None => return None,
+        };
+        indices.push(index);
+    }
+    let mut colours = vec![0; palette.len() * 3];
+    for (rgb, index) in palette {
+        colours[index as usize * 3..][..3].copy_from_slice(&rgb);
+    }
+    Some((indices, colours))
+}
@@ -0,0 +1,161 @@
+use std::io::{IsTerminal, Stdout, Write};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+
+use crate::render::{Image, Rgb};
+
+const HELP: &str = "space: pause/resume  n: next frame  +/-: faster/slower  q: stop animating";
+
+const MAX_FPS: u32 = 1000;
+
+/// Draws frames in the terminal as they are recorded, two cells per character.
+pub(super) struct Player {
+    out: Stdout,
+    fps: u32,
+    paused: bool,
+    frames: usize,
+}
+
+impl Player {
+    pub(super) fn start(fps: u32) -> Result<Self> {
+        let out = std::io::stdout();
+        if !out.is_terminal() {
+            return Err(anyhow!("--animate needs stdout to be a terminal"));
+        }
+        terminal::enable_raw_mode().context("enabling raw mode")?;
+        // the player restores the terminal when dropped, even if entering the screen fails
+        let mut player = Self {
+            out,
+            fps: fps.clamp(1, MAX_FPS),
+            paused: false,
+            frames: 0,
+        };
+        execute!(player.out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(player)
+    }
+
+    /// Draw a frame and wait until the next one is due. Returns false once the user stops the
+    /// animation.
+    pub(super) fn show(&mut self, image: &Image, step: usize) -> Result<bool> {
+        self.frames += 1;
+        self.draw(image)?;
+        let mut deadline = Instant::now() + self.frame_time();
+        loop {
+            self.status(&format!("step {step}"))?;
+            let event = if self.paused {
+                event::read()?
+            } else {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                if !event::poll(timeout)? {
+                    return Ok(true);
+                }
+                event::read()?
+            };
+            let Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) = event
+            else {
+                continue;
+            };
+            match code {
+                KeyCode::Char(' ') | KeyCode::Char('p') => {
+                    self.paused = !self.paused;
+                    deadline = Instant::now() + self.frame_time();
+                }
+                KeyCode::Char('n') | KeyCode::Right if self.paused => return Ok(true),
+                KeyCode::Char('+') | KeyCode::Char('=') => self.fps = (self.fps * 2).min(MAX_FPS),
+                KeyCode::Char('-') => self.fps = (self.fps / 2).max(1),
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(false),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+
+    /// Show the final state until a key is pressed.
+    pub(super) fn end(&mut self, image: &Image) -> Result<()> {
+        self.draw(image)?;
+        queue!(
+            self.out,
+            terminal::Clear(terminal::ClearType::FromCursorDown),
+            Print(format!("done after {} frames, press any key to exit", self.frames))
+        )?;
+        self.out.flush()?;
+        loop {
+            if let Event::Key(KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            }) = event::read()?
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    fn frame_time(&self) -> Duration {
+        Duration::from_secs(1) / self.fps
+    }
+
+    // Each character covers two rows of cells: the upper half block takes the colour of the top
+    // cell and its background the colour of the bottom one.
+    fn draw(&mut self, image: &Image) -> Result<()> {
+        queue!(self.out, cursor::MoveTo(0, 0))?;
+        let mut colours: Option<(Rgb, Rgb)> = None;
+        for y in (0..image.height()).step_by(2) {
+            for x in 0..image.width() {
+                let top = image.get((x, y));
+                let bottom = if y + 1 < image.height() {
+                    image.get((x, y + 1))
+                } else {
+                    [0, 0, 0]
+                };
+                if colours != Some((top, bottom)) {
+                    queue!(
+                        self.out,
+                        SetForegroundColor(colour(top)),
+                        SetBackgroundColor(colour(bottom))
+                    )?;
+                    colours = Some((top, bottom));
+                }
+                queue!(self.out, Print('▀'))?;
+            }
+            queue!(self.out, ResetColor, Print("\r\n"))?;
+            colours = None;
+        }
+        Ok(())
+    }
+
+    fn status(&mut self, message: &str) -> Result<()> {
+        let state = if self.paused { "paused" } else { "playing" };
+        queue!(
+            self.out,
+            terminal::Clear(terminal::ClearType::FromCursorDown),
+            cursor::SavePosition,
+            Print(format!(
+                "{message}  frame {}  {} fps  {state}\r\n{HELP}",
+                self.frames, self.fps
+            )),
+            cursor::RestorePosition
+        )?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        _ = execute!(self.out, ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+        _ = terminal::disable_raw_mode();
+    }
+}
+
+fn colour([r, g, b]: Rgb) -> Color {
+    Color::Rgb { r, g, b }
+}
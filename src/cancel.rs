@@ -0,0 +1,88 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// Solutions only stop if they check for cancellation in their long loops, which is cheap enough
+// to do at every iteration. They check the token of their own run, found in a thread-local so
+// that the days don't have to pass it around, and so that concurrent runs (e.g. tests) don't
+// cancel each other.
+thread_local! {
+    static CURRENT: RefCell<Option<Token>> = const { RefCell::new(None) };
+}
+
+/// Cancellation of a single run, shared between the solution and whoever may cancel it.
+#[derive(Debug, Clone, Default)]
+pub struct Token(Arc<AtomicBool>);
+
+impl Token {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the solution running with this token to stop at its next check.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Error returned by [`check`] once the solution has been cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Run `f` on this thread as part of the run cancelled with `token`.
+pub fn with_token<T>(token: &Token, f: impl FnOnce() -> T) -> T {
+    let previous = CURRENT.with(|current| current.replace(Some(token.clone())));
+    let value = f();
+    CURRENT.with(|current| *current.borrow_mut() = previous);
+    value
+}
+
+/// The token of the run on this thread, to hand over to the threads it spreads work to.
+pub fn current() -> Option<Token> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Whether the run on this thread was cancelled. Never, outside of [`with_token`].
+pub fn is_cancelled() -> bool {
+    CURRENT.with(|current| current.borrow().as_ref().is_some_and(Token::is_cancelled))
+}
+
+/// Fail with [`Cancelled`] once the solution has been cancelled.
+pub fn check() -> Result<(), Cancelled> {
+    match is_cancelled() {
+        true => Err(Cancelled),
+        false => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancels_its_own_run_only() {
+        let (cancelled, running) = (Token::new(), Token::new());
+        cancelled.cancel();
+        assert_eq!(with_token(&cancelled, check), Err(Cancelled));
+        assert_eq!(with_token(&running, check), Ok(()));
+        assert_eq!(check(), Ok(()));
+
+        let handed_over = with_token(&cancelled, || current().unwrap());
+        assert!(std::thread::spawn(move || with_token(&handed_over, is_cancelled))
+            .join()
+            .unwrap());
+    }
+}
@@ -0,0 +1,65 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+/// Where the puzzle input comes from.
+///
+/// Solutions either read the whole input at once with [`Input::read_to_string`], or opt into
+/// streaming it line by line with [`Input::lines`], which keeps memory bounded on very large
+/// inputs.
+#[derive(Debug, Clone)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Input {
+    fn reader(&self) -> Result<Box<dyn BufRead>> {
+        let reader: Box<dyn BufRead> = match self {
+            Self::Stdin => Box::new(BufReader::new(std::io::stdin())),
+            Self::File(path) => Box::new(BufReader::new(
+                File::open(path).with_context(|| format!("opening input file '{}'", path.display()))?,
+            )),
+        };
+        Ok(reader)
+    }
+
+    pub fn read_to_string(&self) -> Result<String> {
+        let mut buf = String::new();
+        self.reader()?
+            .read_to_string(&mut buf)
+            .with_context(|| format!("reading {self}"))?;
+        Ok(buf)
+    }
+
+    /// Iterate over the lines of the input without loading it all in memory.
+    pub fn lines(&self) -> Result<impl Iterator<Item = Result<String>>> {
+        let name = self.to_string();
+        Ok(self
+            .reader()?
+            .lines()
+            .map(move |line| line.with_context(|| format!("reading {name}"))))
+    }
+
+    /// Stream the input as [`Input::lines`], parsing each line with `parse`. Reading and parsing
+    /// happen in a `parse` span, so that traces tell them apart from the solving they interleave
+    /// with.
+    pub fn parse_lines<T>(&self, mut parse: impl FnMut(&str) -> Result<T>) -> Result<impl Iterator<Item = Result<T>>> {
+        let span = tracing::info_span!("parse");
+        let mut lines = self.lines()?;
+        Ok(std::iter::from_fn(move || {
+            span.in_scope(|| lines.next().map(|line| parse(&line?)))
+        }))
+    }
+}
+
+impl std::fmt::Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stdin => write!(f, "stdin"),
+            Self::File(path) => write!(f, "input file '{}'", path.display()),
+        }
+    }
+}
@@ -1,3 +1,14 @@
+pub mod alloc;
+pub mod animation;
+pub mod cancel;
+pub mod input;
+pub mod math;
+pub mod parallel;
+pub mod region;
+pub mod render;
+pub mod rng;
+pub mod word_search;
+
 use anyhow::{anyhow, Context, Result};
 
 pub fn split2<T>(val: &str) -> Result<(T, T)>
@@ -21,3 +32,22 @@ where
         right.parse::<T>().context("parsing right")?,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split2_parses_both_columns() {
+        assert_eq!(split2::<u32>("3   4").unwrap(), (3, 4));
+        assert_eq!(split2::<i64>("  -1\t2 ").unwrap(), (-1, 2));
+    }
+
+    #[test]
+    fn split2_rejects_invalid_lines() {
+        assert!(split2::<u32>("").is_err());
+        assert!(split2::<u32>("3").is_err());
+        assert!(split2::<u32>("3 4 5").is_err());
+        assert!(split2::<u32>("3 x").is_err());
+    }
+}
@@ -1,11 +1,21 @@
 mod year2024;
 
-use std::io::Read;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
 
-use anyhow::{anyhow, Context, Result};
+use adventofcode::input::Input;
+use adventofcode::{alloc, cancel, parallel};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::{Parser, Subcommand};
 use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::filter_fn;
 use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
 
 /// devodev's Advent of Code solver CLI.
 #[derive(Parser)]
@@ -22,8 +32,45 @@ struct Cli {
     /// Enable info(-v), debug(-vv) or trace(-vvv) logging
     #[arg(long, short = 'v', action = clap::ArgAction::Count)]
     verbose: u8,
+
+    /// Record every span to this file in the Chrome trace event format, to be opened with
+    /// https://ui.perfetto.dev or chrome://tracing.
+    #[arg(long)]
+    trace_file: Option<PathBuf>,
+
+    /// Record the time spent in every stack of spans to this file as folded stacks, the input of
+    /// flamegraph tools such as `inferno-flamegraph`.
+    #[arg(long)]
+    folded_file: Option<PathBuf>,
+
+    /// Give up on the solution after this long, e.g. `30s`, `500ms` or `2m`. The process then
+    /// exits with status 124, like `timeout`.
+    #[arg(long, value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Number of threads solving the days made of independent items, one per core by default.
+    /// Only builds with the `parallel` feature use more than one.
+    #[arg(long)]
+    threads: Option<usize>,
 }
 
+// Exit status when the solution timed out.
+const TIMEOUT_STATUS: i32 = 124;
+
+// Once timed out, how long the solution has to notice it was cancelled.
+const CANCEL_GRACE: Duration = Duration::from_millis(200);
+
+#[derive(Debug)]
+struct TimedOut(Duration);
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timed out after {:?}", self.0)
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
 #[derive(Debug, Subcommand)]
 enum Commands {
     /// Advent of Code 2024.
@@ -32,50 +79,168 @@ enum Commands {
 }
 
 impl Cli {
-    #[tracing::instrument(skip_all)]
-    fn read_input(&self) -> Result<String> {
+    // The input is only opened here: solutions decide whether to read it all at once or stream it.
+    fn input(&self) -> Result<Input> {
         let input = match self.input.as_str() {
             "" => return Err(anyhow!("input flag cannot be empty")),
-            "-" => {
-                let mut buf = String::new();
-                std::io::stdin().read_to_string(&mut buf).context("reading stdin")?;
-                buf
-            }
-            path => std::fs::read_to_string(&self.input).with_context(|| format!("reading input file '{path}'"))?,
+            "-" => Input::Stdin,
+            path => Input::File(path.into()),
         };
         Ok(input)
     }
 
     #[tracing::instrument(skip_all)]
-    fn run(self, input: String) -> Result<()> {
+    fn run(self, input: Input) -> Result<()> {
         match self.command {
             Commands::Year2024(args) => args.run(input),
         }
     }
+
+    fn run_with_timeout(self, input: Input, timeout: Duration) -> Result<()> {
+        with_timeout(timeout, move || self.run(input))
+    }
+}
+
+// The solution runs on a worker thread, so that it can be abandoned when it takes too long. It is
+// cancelled too, and stops early if it checks for cancellation.
+fn with_timeout(timeout: Duration, solution: impl FnOnce() -> Result<()> + Send + 'static) -> Result<()> {
+    let token = cancel::Token::new();
+    let run = token.clone();
+    let (tx, rx) = mpsc::channel();
+    let worker = thread::Builder::new()
+        .name(String::from("solution"))
+        .spawn(move || _ = tx.send(cancel::with_token(&run, solution)))
+        .context("starting solution thread")?;
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            token.cancel();
+            match rx.recv_timeout(CANCEL_GRACE) {
+                Ok(Ok(())) => Ok(()),
+                _ => Err(TimedOut(timeout).into()),
+            }
+        }
+        // the solution panicked, and the panic was already reported
+        Err(RecvTimeoutError::Disconnected) => match worker.join() {
+            Err(panic) => std::panic::resume_unwind(panic),
+            Ok(()) => unreachable!("the solution thread always sends its result"),
+        },
+    }
+}
+
+// A number of seconds, or a number followed by `ms`, `s` or `m`.
+fn parse_duration(s: &str) -> Result<Duration> {
+    let (value, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let value: f64 = value.parse().with_context(|| format!("invalid duration '{s}'"))?;
+    let seconds = match unit {
+        "ms" => value / 1000.0,
+        "s" => value,
+        "m" => value * 60.0,
+        _ => bail!("invalid duration unit '{unit}' (use ms, s or m)"),
+    };
+    Duration::try_from_secs_f64(seconds).with_context(|| format!("invalid duration '{s}'"))
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    setup_tracing(cli.verbose);
+    let guards = setup_tracing(&cli)?;
+
+    let input = cli.input()?;
+    if let Some(threads) = cli.threads {
+        parallel::set_threads(threads)?;
+    }
+    let (result, allocations) = alloc::measure(|| match cli.timeout {
+        Some(timeout) => cli.run_with_timeout(input, timeout),
+        None => cli.run(input),
+    });
+    if let Some(allocations) = allocations {
+        eprintln!("{allocations}");
+    }
 
-    let input = cli.read_input()?;
-    match cli.run(input) {
+    match result {
         Ok(_) => Ok(()),
+        // the solution may still be running, exit without waiting for it
+        Err(err) if err.is::<TimedOut>() => {
+            eprintln!("Error: {err}");
+            drop(guards);
+            std::process::exit(TIMEOUT_STATUS);
+        }
         Err(err) => Err(anyhow!("{err:#}")),
     }
 }
 
-fn setup_tracing(verbose: u8) {
-    let level = match verbose {
+// Flush the trace files when dropped, once the solution is done.
+struct TraceGuards {
+    _chrome: Option<tracing_chrome::FlushGuard>,
+    _folded: Option<tracing_flame::FlushGuard<BufWriter<File>>>,
+}
+
+// Logs go to stderr at the requested verbosity. The trace files get every span regardless of the
+// verbosity, and no events.
+fn setup_tracing(cli: &Cli) -> Result<TraceGuards> {
+    let level = match cli.verbose {
         1 => LevelFilter::INFO,
         2 => LevelFilter::DEBUG,
         3 => LevelFilter::TRACE,
         _ => LevelFilter::WARN,
     };
-    let stderr_subscriber = tracing_subscriber::fmt()
+    let stderr_layer = tracing_subscriber::fmt::layer()
         .with_writer(std::io::stderr)
-        .with_max_level(level)
         .with_span_events(FmtSpan::ENTER | FmtSpan::CLOSE)
-        .finish();
-    tracing::subscriber::set_global_default(stderr_subscriber).expect("setting tracing global default failed");
+        .with_filter(level);
+
+    let (chrome_layer, chrome_guard) = match &cli.trace_file {
+        Some(path) => {
+            let file = File::create(path).with_context(|| format!("creating trace file '{}'", path.display()))?;
+            let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+                .writer(file)
+                .include_args(true)
+                .build();
+            (Some(layer.with_filter(filter_fn(|meta| meta.is_span()))), Some(guard))
+        }
+        None => (None, None),
+    };
+    let (folded_layer, folded_guard) = match &cli.folded_file {
+        Some(path) => {
+            let (layer, guard) = tracing_flame::FlameLayer::with_file(path)
+                .with_context(|| format!("creating folded stacks file '{}'", path.display()))?;
+            let layer = layer.with_empty_samples(false).with_file_and_line(false);
+            (Some(layer.with_filter(filter_fn(|meta| meta.is_span()))), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    let subscriber = tracing_subscriber::registry()
+        .with(stderr_layer)
+        .with(chrome_layer)
+        .with(folded_layer);
+    tracing::subscriber::set_global_default(subscriber).expect("setting tracing global default failed");
+    Ok(TraceGuards {
+        _chrome: chrome_guard,
+        _folded: folded_guard,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_again_after_a_timeout() {
+        let spin = || loop {
+            cancel::check()?;
+            thread::sleep(Duration::from_millis(1));
+        };
+        let err = with_timeout(Duration::from_millis(20), spin).unwrap_err();
+        assert!(err.is::<TimedOut>());
+
+        let run = || {
+            cancel::check()?;
+            Ok(())
+        };
+        with_timeout(Duration::from_secs(5), run).unwrap();
+    }
 }
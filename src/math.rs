@@ -0,0 +1,55 @@
+/// Greatest common divisor.
+pub fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Least common multiple.
+pub fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+/// Returns `(g, x, y)` such that `ax + by = g = gcd(a, b)`, with `g > 0`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_s, mut s) = (1, 0);
+    let (mut old_t, mut t) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+        (old_t, t) = (t, old_t - q * t);
+    }
+    if old_r < 0 {
+        (-old_r, -old_s, -old_t)
+    } else {
+        (old_r, old_s, old_t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(gcd(101, 103), 1);
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(lcm(101, 103), 10403);
+        assert_eq!(lcm(4, 6), 12);
+    }
+
+    #[test]
+    fn extended_gcd_finds_bezout_coefficients() {
+        for (a, b) in [(240, 46), (-240, 46), (240, -46), (7, 0), (0, -7), (101, 103)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert!(g > 0);
+            assert_eq!(a * x + b * y, g, "{a} {b}");
+            assert_eq!(a % g, 0);
+            assert_eq!(b % g, 0);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// Days made of independent items (reports, candidate obstructions, equations, machines) map them
+// through this, so that the `parallel` feature can spread them over a work-stealing pool without
+// changing the days.
+
+use anyhow::Result;
+
+/// Solve on `threads` threads rather than one per core, e.g. to compare with a single one. Must
+/// be called before any item is mapped.
+#[cfg(feature = "parallel")]
+pub fn set_threads(threads: usize) -> Result<()> {
+    use anyhow::Context;
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .context("setting up the thread pool")
+}
+
+/// Serial builds only ever solve on one thread.
+#[cfg(not(feature = "parallel"))]
+pub fn set_threads(threads: usize) -> Result<()> {
+    match threads {
+        1 => Ok(()),
+        _ => anyhow::bail!("solving on {threads} threads needs the `parallel` feature"),
+    }
+}
+
+/// Apply `f` to every item, keeping their order.
+///
+/// Items are mapped lazily one at a time, unless the `parallel` feature is enabled: then they are
+/// all collected first and mapped on every core.
+#[cfg(not(feature = "parallel"))]
+pub fn map<I, R, F>(items: I, f: F) -> impl Iterator<Item = R>
+where
+    I: IntoIterator,
+    I::Item: Send,
+    R: Send,
+    F: Fn(I::Item) -> R + Sync + Send,
+{
+    items.into_iter().map(f)
+}
+
+/// Apply `f` to every item on every core, keeping their order. Items are all collected first.
+#[cfg(feature = "parallel")]
+pub fn map<I, R, F>(items: I, f: F) -> impl Iterator<Item = R>
+where
+    I: IntoIterator,
+    I::Item: Send,
+    R: Send,
+    F: Fn(I::Item) -> R + Sync + Send,
+{
+    use rayon::prelude::*;
+
+    // the pool's threads check the cancellation of the run handing them the items
+    let token = crate::cancel::current().unwrap_or_default();
+    let items: Vec<_> = items.into_iter().collect();
+    let results: Vec<R> = items
+        .into_par_iter()
+        .map(|item| crate::cancel::with_token(&token, || f(item)))
+        .collect();
+    results.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_order() {
+        let squares: Vec<u64> = map(1..=1000u64, |n| n * n).collect();
+        assert_eq!(squares, (1..=1000u64).map(|n| n * n).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::VecDeque;
+
+/// A 4-connected group of cells sharing the same label.
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub label: char,
+    pub cells: Vec<(usize, usize)>,
+    pub perimeter: usize,
+    pub sides: usize,
+    /// Inclusive bounding box as `((min_x, min_y), (max_x, max_y))`.
+    pub bounding_box: ((usize, usize), (usize, usize)),
+    /// Number of holes: 4-connected areas of cells outside the region (of any other label) that
+    /// can't reach the outside of its bounding box without crossing it. Cells touching only
+    /// diagonally are separate holes.
+    pub holes: usize,
+}
+
+/// Extract every connected region of a `char` grid, in row-major order of discovery.
+pub fn regions(grid: &[Vec<char>]) -> Vec<Region> {
+    let height = grid.len();
+    let mut visited: Vec<Vec<bool>> = grid.iter().map(|r| vec![false; r.len()]).collect();
+
+    let mut regions = Vec::new();
+    for y in 0..height {
+        for x in 0..grid[y].len() {
+            if visited[y][x] {
+                continue;
+            }
+            visited[y][x] = true;
+            let label = grid[y][x];
+
+            let mut cells = Vec::new();
+            let mut queue = VecDeque::new();
+            queue.push_back((x, y));
+            while let Some((x, y)) = queue.pop_front() {
+                cells.push((x, y));
+                for (nx, ny) in neighbours((x as isize, y as isize)) {
+                    if label_at(grid, (nx, ny)) != Some(label) {
+                        continue;
+                    }
+                    let (nx, ny) = (nx as usize, ny as usize);
+                    if visited[ny][nx] {
+                        continue;
+                    }
+                    visited[ny][nx] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+            regions.push(Region::new(grid, label, cells));
+        }
+    }
+    regions
+}
+
+impl Region {
+    pub fn area(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn new(grid: &[Vec<char>], label: char, cells: Vec<(usize, usize)>) -> Self {
+        let contains = |(x, y): (isize, isize)| label_at(grid, (x, y)) == Some(label);
+
+        let mut perimeter = 0;
+        let mut sides = 0;
+        for (x, y) in cells.iter().map(|(x, y)| (*x as isize, *y as isize)) {
+            perimeter += neighbours((x, y)).filter(|n| !contains(*n)).count();
+
+            // A region has as many sides as it has corners. For every cell, look at each of its
+            // four corners:
+            // - convex corner: both orthogonal neighbours are outside the region.
+            // - concave corner: both orthogonal neighbours are inside, but the diagonal is not.
+            for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                let horizontal = contains((x + dx, y));
+                let vertical = contains((x, y + dy));
+                let diagonal = contains((x + dx, y + dy));
+                if (!horizontal && !vertical) || (horizontal && vertical && !diagonal) {
+                    sides += 1;
+                }
+            }
+        }
+
+        let bounding_box = cells
+            .iter()
+            .fold(((usize::MAX, usize::MAX), (0, 0)), |(min, max), (x, y)| {
+                ((min.0.min(*x), min.1.min(*y)), (max.0.max(*x), max.1.max(*y)))
+            });
+        let holes = count_holes(&cells, bounding_box);
+
+        Self {
+            label,
+            cells,
+            perimeter,
+            sides,
+            bounding_box,
+            holes,
+        }
+    }
+}
+
+// Flood fill the complement of the region inside its bounding box padded by one cell, starting
+// from the padding and moving as the region's own cells do, horizontally and vertically. Whatever
+// complement is left unreached is enclosed, and each of its 4-connected components is a hole.
+fn count_holes(cells: &[(usize, usize)], ((min_x, min_y), (max_x, max_y)): ((usize, usize), (usize, usize))) -> usize {
+    let (width, height) = (max_x - min_x + 3, max_y - min_y + 3);
+    let mut blocked = vec![vec![false; width]; height];
+    for (x, y) in cells {
+        blocked[y - min_y + 1][x - min_x + 1] = true;
+    }
+
+    let fill = |blocked: &mut Vec<Vec<bool>>, start: (usize, usize)| {
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        blocked[start.1][start.0] = true;
+        while let Some((x, y)) = queue.pop_front() {
+            for (dx, dy) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if blocked[ny][nx] {
+                    continue;
+                }
+                blocked[ny][nx] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    };
+
+    fill(&mut blocked, (0, 0));
+    let mut holes = 0;
+    for y in 0..height {
+        for x in 0..width {
+            if !blocked[y][x] {
+                holes += 1;
+                fill(&mut blocked, (x, y));
+            }
+        }
+    }
+    holes
+}
+
+fn label_at(grid: &[Vec<char>], (x, y): (isize, isize)) -> Option<char> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    grid.get(y as usize).and_then(|row| row.get(x as usize)).copied()
+}
+
+fn neighbours((x, y): (isize, isize)) -> impl Iterator<Item = (isize, isize)> {
+    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Vec<Vec<char>> {
+        rows.iter().map(|r| r.chars().collect()).collect()
+    }
+
+    fn region_at(regions: &[Region], cell: (usize, usize)) -> &Region {
+        regions.iter().find(|r| r.cells.contains(&cell)).unwrap()
+    }
+
+    #[test]
+    fn counts_sides_of_e_shape() {
+        let regions = regions(&grid(&["EEEEE", "EXXXX", "EEEEE", "EXXXX", "EEEEE"]));
+        let e = region_at(&regions, (0, 0));
+        assert_eq!((e.area(), e.perimeter, e.sides), (17, 36, 12));
+        for x in [(1, 1), (1, 3)] {
+            assert_eq!(region_at(&regions, x).sides, 4);
+        }
+    }
+
+    #[test]
+    fn counts_sides_when_touching_diagonally() {
+        let regions = regions(&grid(&["AAAAAA", "AAABBA", "AAABBA", "ABBAAA", "ABBAAA", "AAAAAA"]));
+        let a = region_at(&regions, (0, 0));
+        assert_eq!((a.area(), a.sides), (28, 12));
+        assert_eq!(regions.iter().map(|r| r.area() * r.sides).sum::<usize>(), 368);
+    }
+
+    #[test]
+    fn counts_enclosed_regions_as_holes() {
+        let regions = regions(&grid(&["OOOOO", "OXOXO", "OOOOO", "OXOXO", "OOOOO"]));
+        assert_eq!(region_at(&regions, (0, 0)).holes, 4);
+        assert_eq!(region_at(&regions, (1, 1)).holes, 0);
+
+        let regions = super::regions(&grid(&["AAAAA", "ABBBA", "ABCBA", "ABBBA", "AAAAA"]));
+        assert_eq!(region_at(&regions, (0, 0)).holes, 1);
+        assert_eq!(region_at(&regions, (1, 1)).holes, 1);
+    }
+
+    #[test]
+    fn diagonal_gaps_are_separate_holes() {
+        // the two enclosed cells only touch diagonally
+        let regions = regions(&grid(&["AAAA", "A.AA", "AA.A", "AAAA"]));
+        assert_eq!(region_at(&regions, (0, 0)).holes, 2);
+
+        // so do the two B regions
+        let regions = super::regions(&grid(&["AAAAAA", "AAABBA", "AAABBA", "ABBAAA", "ABBAAA", "AAAAAA"]));
+        assert_eq!(region_at(&regions, (0, 0)).holes, 2);
+    }
+
+    #[test]
+    fn cells_touching_the_outside_diagonally_are_enclosed() {
+        let regions = regions(&grid(&["AAA.", "A.A.", "AA..", "...."]));
+        assert_eq!(region_at(&regions, (0, 0)).holes, 1);
+    }
+
+    #[test]
+    fn bounding_box_spans_all_cells() {
+        let regions = regions(&grid(&["....", ".L..", ".L..", ".LLL"]));
+        assert_eq!(region_at(&regions, (1, 1)).bounding_box, ((1, 1), (3, 3)));
+        assert_eq!(region_at(&regions, (0, 0)).bounding_box, ((0, 0), (3, 3)));
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+
+pub type Rgb = [u8; 3];
+
+pub const BACKGROUND: Rgb = [15, 15, 35];
+pub const WALL: Rgb = [120, 120, 140];
+pub const VISITED: Rgb = [40, 90, 160];
+pub const HIGHLIGHT: Rgb = [255, 215, 0];
+pub const ACTOR: Rgb = [220, 50, 50];
+pub const OBJECT: Rgb = [140, 90, 40];
+pub const GREEN: Rgb = [0, 170, 60];
+
+// Images are scaled up so that their largest side is about this many pixels.
+const TARGET_SIZE: usize = 512;
+
+/// Maps the characters of an ASCII rendering to colours.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    colours: HashMap<char, Rgb>,
+    default: Rgb,
+}
+
+impl Palette {
+    pub fn new(default: Rgb) -> Self {
+        Self {
+            colours: HashMap::new(),
+            default,
+        }
+    }
+
+    pub fn with(mut self, c: char, colour: Rgb) -> Self {
+        self.colours.insert(c, colour);
+        self
+    }
+
+    pub fn colour(&self, c: char) -> Rgb {
+        self.colours.get(&c).copied().unwrap_or(self.default)
+    }
+}
+
+/// A grid of coloured cells, saved as one square of pixels per cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    cells: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![background; width * height],
+        }
+    }
+
+    pub fn from_grid<T>(grid: &[Vec<T>], colour: impl Fn(&T) -> Rgb) -> Self {
+        let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+        let mut image = Self::new(width, grid.len(), BACKGROUND);
+        for (y, row) in grid.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                image.set((x, y), colour(cell));
+            }
+        }
+        image
+    }
+
+    /// Colour an ASCII rendering, one cell per character.
+    pub fn from_text(text: &str, palette: &Palette) -> Self {
+        let grid: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+        Self::from_grid(&grid, |c| palette.colour(*c))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Cells out of the image are ignored.
+    pub fn set(&mut self, (x, y): (usize, usize), colour: Rgb) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = colour;
+        }
+    }
+
+    pub fn get(&self, (x, y): (usize, usize)) -> Rgb {
+        self.cells[y * self.width + x]
+    }
+
+    /// Number of pixels per cell side for an image that is neither tiny nor huge.
+    pub fn default_scale(&self) -> usize {
+        (TARGET_SIZE / self.width.max(self.height).max(1)).clamp(1, 16)
+    }
+
+    /// RGB pixels, row by row, with each cell drawn as a `scale`x`scale` square.
+    pub fn pixels(&self, scale: usize) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(self.width * self.height * scale * scale * 3);
+        for y in 0..self.height * scale {
+            for x in 0..self.width * scale {
+                pixels.extend(self.get((x / scale, y / scale)));
+            }
+        }
+        pixels
+    }
+
+    /// Save as PNG or PPM, depending on the extension of `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let extension = path.extension().and_then(|e| e.to_str());
+        if !matches!(extension, Some("png" | "ppm")) {
+            return Err(anyhow!(
+                "unsupported image format for '{}': use .png or .ppm",
+                path.display()
+            ));
+        }
+        let file = File::create(path).with_context(|| format!("creating '{}'", path.display()))?;
+        let mut out = BufWriter::new(file);
+        let scale = self.default_scale();
+        match extension {
+            Some("png") => self.write_png(&mut out, scale),
+            _ => self.write_ppm(&mut out, scale),
+        }
+        .with_context(|| format!("writing '{}'", path.display()))?;
+        out.flush()?;
+        Ok(())
+    }
+
+    // Binary portable pixmap: a tiny text header followed by the raw RGB bytes.
+    pub fn write_ppm(&self, out: &mut impl Write, scale: usize) -> Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width * scale, self.height * scale)?;
+        out.write_all(&self.pixels(scale))?;
+        Ok(())
+    }
+
+    pub fn write_png(&self, out: &mut impl Write, scale: usize) -> Result<()> {
+        let mut encoder = png::Encoder::new(out, (self.width * scale) as u32, (self.height * scale) as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels(scale))?;
+        writer.finish()?;
+        Ok(())
+    }
+}
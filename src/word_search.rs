@@ -0,0 +1,308 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Self::North,
+        Self::NorthEast,
+        Self::East,
+        Self::SouthEast,
+        Self::South,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+    ];
+
+    pub fn vector(&self) -> (isize, isize) {
+        match self {
+            Self::North => (0, -1),
+            Self::NorthEast => (1, -1),
+            Self::East => (1, 0),
+            Self::SouthEast => (1, 1),
+            Self::South => (0, 1),
+            Self::SouthWest => (-1, 1),
+            Self::West => (-1, 0),
+            Self::NorthWest => (-1, -1),
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::North => "N",
+            Self::NorthEast => "NE",
+            Self::East => "E",
+            Self::SouthEast => "SE",
+            Self::South => "S",
+            Self::SouthWest => "SW",
+            Self::West => "W",
+            Self::NorthWest => "NW",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A rectangular grid of letters to search in.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    cells: Vec<Vec<char>>,
+}
+
+impl FromStr for Grid {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let cells: Vec<Vec<char>> = s
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.trim().chars().collect())
+            .collect();
+        if cells.windows(2).any(|w| w[0].len() != w[1].len()) {
+            return Err(anyhow!("invalid input formatting: rows have different lengths"));
+        }
+        Ok(Self { cells })
+    }
+}
+
+/// An occurrence of `words[word]` starting at `pos` and read towards `direction`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordMatch {
+    pub word: usize,
+    pub pos: (usize, usize),
+    pub direction: Direction,
+}
+
+/// An occurrence of a pattern with its top-left corner at `pos`, after `rotation` quarter turns
+/// clockwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternMatch {
+    pub pos: (usize, usize),
+    pub rotation: usize,
+}
+
+impl Grid {
+    pub fn width(&self) -> usize {
+        self.cells.first().map(Vec::len).unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn get(&self, (x, y): (isize, isize)) -> Option<char> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        self.cells.get(y as usize).and_then(|row| row.get(x as usize)).copied()
+    }
+
+    /// Find every occurrence of every word, in all 8 directions.
+    ///
+    /// Matches covering the same cells are only reported once (the first one found), so a
+    /// palindrome isn't counted again when read backwards, nor a single letter once per direction.
+    pub fn find_words<S: AsRef<str>>(&self, words: &[S]) -> Vec<WordMatch> {
+        let words: Vec<Vec<char>> = words.iter().map(|w| w.as_ref().chars().collect()).collect();
+        // a straight run of cells is identified by its two ends, whichever way it is read
+        let mut seen: HashSet<((isize, isize), (isize, isize))> = HashSet::new();
+        let mut matches = Vec::new();
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                for (word_idx, word) in words.iter().enumerate() {
+                    if word.first() != Some(&self.cells[y][x]) {
+                        continue;
+                    }
+                    for direction in Direction::ALL {
+                        let (dx, dy) = direction.vector();
+                        let found = word.iter().enumerate().all(|(i, c)| {
+                            let i = i as isize;
+                            self.get((x as isize + dx * i, y as isize + dy * i)) == Some(*c)
+                        });
+                        let len = word.len() as isize - 1;
+                        let (start, end) = ((x as isize, y as isize), (x as isize + dx * len, y as isize + dy * len));
+                        if found && seen.insert((start.min(end), start.max(end))) {
+                            matches.push(WordMatch {
+                                word: word_idx,
+                                pos: (x, y),
+                                direction,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    /// Find every placement of `pattern` in the grid, trying each of its distinct rotations.
+    pub fn find_pattern(&self, pattern: &Pattern) -> Vec<PatternMatch> {
+        let rotations = pattern.rotations();
+        let mut matches = Vec::new();
+        for (rotation, pattern) in rotations.iter().enumerate() {
+            if pattern.width() > self.width() || pattern.height() > self.height() {
+                continue;
+            }
+            for y in 0..=self.height() - pattern.height() {
+                for x in 0..=self.width() - pattern.width() {
+                    if pattern.matches_at(self, (x, y)) {
+                        matches.push(PatternMatch { pos: (x, y), rotation });
+                    }
+                }
+            }
+        }
+        matches
+    }
+}
+
+/// A small 2D template where `.` matches any letter. A backslash escapes the next character, so
+/// `\.` only matches a literal `.` (and `\\` a backslash).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    cells: Vec<Vec<Option<char>>>,
+}
+
+impl FromStr for Pattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let cells: Vec<Vec<Option<char>>> = s
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(parse_row)
+            .collect::<Result<_, _>>()?;
+        if cells.is_empty() || cells.windows(2).any(|w| w[0].len() != w[1].len()) {
+            return Err(anyhow!("invalid pattern: must be a non-empty rectangle"));
+        }
+        Ok(Self { cells })
+    }
+}
+
+fn parse_row(row: &str) -> anyhow::Result<Vec<Option<char>>> {
+    let mut cells = Vec::new();
+    let mut chars = row.chars();
+    while let Some(c) = chars.next() {
+        cells.push(match c {
+            '\\' => Some(
+                chars
+                    .next()
+                    .ok_or_else(|| anyhow!("invalid pattern: '\\' at the end of '{row}'"))?,
+            ),
+            '.' => None,
+            c => Some(c),
+        });
+    }
+    Ok(cells)
+}
+
+impl Pattern {
+    fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    // Quarter turn clockwise: the first column, read bottom to top, becomes the first row.
+    fn rotate(&self) -> Self {
+        let cells = (0..self.width())
+            .map(|x| (0..self.height()).rev().map(|y| self.cells[y][x]).collect())
+            .collect();
+        Self { cells }
+    }
+
+    // Symmetric patterns would otherwise be matched more than once at the same place. Rotations
+    // cycle, so the first repeated one means every following one is a repeat too.
+    fn rotations(&self) -> Vec<Pattern> {
+        let mut rotations: Vec<Pattern> = vec![self.clone()];
+        for _ in 0..3 {
+            let next = rotations.last().unwrap().rotate();
+            if rotations.contains(&next) {
+                break;
+            }
+            rotations.push(next);
+        }
+        rotations
+    }
+
+    fn matches_at(&self, grid: &Grid, (x, y): (usize, usize)) -> bool {
+        self.cells.iter().enumerate().all(|(dy, row)| {
+            row.iter()
+                .enumerate()
+                .all(|(dx, c)| c.is_none_or(|c| grid.cells[y + dy][x + dx] == c))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(s: &str) -> Grid {
+        Grid::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn counts_words_in_every_direction() {
+        // XMAS read in all 8 directions from the X in the middle
+        let g = grid("S..S..S\n.A.A.A.\n..MMM..\nSAMXMAS\n..MMM..\n.A.A.A.\nS..S..S");
+        let matches = g.find_words(&["XMAS"]);
+        assert_eq!(matches.len(), 8);
+        assert!(matches.iter().all(|m| m.pos == (3, 3)));
+
+        let matches = g.find_words(&["XMAS", "MAS"]);
+        assert_eq!(matches.iter().filter(|m| m.word == 1).count(), 8);
+    }
+
+    #[test]
+    fn palindromes_and_single_letters_count_once() {
+        assert_eq!(grid("ABA").find_words(&["ABA"]).len(), 1);
+        assert_eq!(grid("AA\nAA").find_words(&["A"]).len(), 4);
+        assert_eq!(grid("XMAS").find_words(&["XMAS", "SAMX"]).len(), 1);
+    }
+
+    #[test]
+    fn dot_is_a_wildcard_unless_escaped() {
+        let pattern = Pattern::from_str("A.\n.B").unwrap();
+        assert_eq!(grid("AX\nYB\n").find_pattern(&pattern).len(), 1);
+
+        let literal = Pattern::from_str("A\\.").unwrap();
+        assert_eq!(
+            grid("AX\nA.").find_pattern(&literal),
+            vec![PatternMatch {
+                pos: (0, 1),
+                rotation: 0
+            }]
+        );
+        assert!(Pattern::from_str("A\\").is_err());
+    }
+
+    #[test]
+    fn finds_x_mas_in_every_rotation() {
+        let pattern = Pattern::from_str("M.S\n.A.\nM.S").unwrap();
+        let rotations = ["M.S\n.A.\nM.S", "M.M\n.A.\nS.S", "S.M\n.A.\nS.M", "S.S\n.A.\nM.M"];
+        for (rotation, text) in rotations.iter().enumerate() {
+            let matches = grid(text).find_pattern(&pattern);
+            assert_eq!(matches, vec![PatternMatch { pos: (0, 0), rotation }], "{text}");
+        }
+        // a symmetric pattern is only tried once
+        let symmetric = Pattern::from_str("A.A\n.A.\nA.A").unwrap();
+        assert_eq!(grid("AAA\nAAA\nAAA").find_pattern(&symmetric).len(), 1);
+    }
+}
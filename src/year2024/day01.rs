@@ -1,5 +1,6 @@
 use std::collections::{BinaryHeap, HashMap};
 
+use adventofcode::input::Input;
 use adventofcode::split2;
 use anyhow::Result;
 
@@ -26,7 +27,7 @@ pub enum Commands {
 }
 
 impl Args {
-    pub fn run(self, input: String) -> Result<()> {
+    pub fn run(self, input: Input) -> Result<()> {
         match self.command {
             Commands::Part1BinaryHeap => part1_binary_heap(input),
             Commands::Part1Sort => part1_sort(input),
@@ -35,11 +36,11 @@ impl Args {
     }
 }
 
-fn part1_binary_heap(input: String) -> Result<()> {
+fn part1_binary_heap(input: Input) -> Result<()> {
     let mut col1 = BinaryHeap::new();
     let mut col2 = BinaryHeap::new();
-    for line in input.lines() {
-        let (left, right) = split2::<u32>(line)?;
+    for pair in input.parse_lines(split2::<u32>)? {
+        let (left, right) = pair?;
         col1.push(left);
         col2.push(right);
     }
@@ -53,11 +54,11 @@ fn part1_binary_heap(input: String) -> Result<()> {
     Ok(())
 }
 
-fn part1_sort(input: String) -> Result<()> {
+fn part1_sort(input: Input) -> Result<()> {
     let mut col1 = Vec::new();
     let mut col2 = Vec::new();
-    for line in input.lines() {
-        let (left, right) = split2::<u32>(line)?;
+    for pair in input.parse_lines(split2::<u32>)? {
+        let (left, right) = pair?;
         col1.push(left);
         col2.push(right);
     }
@@ -71,11 +72,11 @@ fn part1_sort(input: String) -> Result<()> {
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
+fn part2(input: Input) -> Result<()> {
     let mut col1 = Vec::new();
     let mut col2: HashMap<u32, u32> = HashMap::new();
-    for line in input.lines() {
-        let (left, right) = split2::<u32>(line)?;
+    for pair in input.parse_lines(split2::<u32>)? {
+        let (left, right) = pair?;
         col1.push(left);
         col2.entry(right).and_modify(|v| *v += 1).or_insert(1);
     }
@@ -1,11 +1,25 @@
-use std::cmp::Ordering;
+use std::ops::RangeInclusive;
 
-use anyhow::Result;
+use adventofcode::input::Input;
+use adventofcode::parallel;
+use anyhow::{anyhow, Context, Result};
 
 #[derive(Debug, clap::Args)]
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Minimum difference between two adjacent levels.
+    #[arg(long, default_value_t = 1, global = true)]
+    min_step: u32,
+
+    /// Maximum difference between two adjacent levels.
+    #[arg(long, default_value_t = 3, global = true)]
+    max_step: u32,
+
+    /// Print each report with the level(s) removed to make it safe, to stderr.
+    #[arg(long, global = true)]
+    explain: bool,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -14,83 +28,192 @@ pub enum Commands {
     Part1,
 
     /// Part 2.
-    Part2,
+    Part2 {
+        /// Maximum number of levels the Problem Dampener may remove from a report.
+        #[arg(long, default_value_t = 1)]
+        removals: usize,
+    },
 }
 
 impl Args {
-    pub fn run(self, input: String) -> Result<()> {
+    pub fn run(self, input: Input) -> Result<()> {
+        if self.min_step > self.max_step {
+            return Err(anyhow!("--min-step must not be greater than --max-step"));
+        }
+        let bounds = self.min_step..=self.max_step;
         match self.command {
-            Commands::Part1 => part1(input),
-            Commands::Part2 => part2(input),
+            Commands::Part1 => part1(input, &bounds, self.explain),
+            Commands::Part2 { removals } => part2(input, removals, &bounds, self.explain),
         }
     }
 }
 
-fn part1(input: String) -> Result<()> {
-    let safe_count: u32 = input
-        .lines()
-        .map(|line| {
-            line.split_whitespace()
-                .map(|l| l.parse::<u32>().expect("parsing level as int"))
-                .collect::<Vec<_>>()
+fn part1(input: Input, bounds: &RangeInclusive<u32>, explain: bool) -> Result<()> {
+    let mut safe_count = 0;
+    let checked = parallel::map(reports(&input)?, |levels| {
+        levels.map(|levels| {
+            let safe = is_safe(&levels, bounds);
+            (levels, safe)
         })
-        .filter_map(|levels| is_safe(&levels).then_some(1))
-        .sum();
+    });
+    for checked in checked {
+        let (levels, safe) = checked?;
+        if explain {
+            eprintln!("{levels:?}: {}", if safe { "safe" } else { "unsafe" });
+        }
+        if safe {
+            safe_count += 1;
+        }
+    }
     println!("{safe_count}");
 
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
-    let safe_count: u32 = input
-        .lines()
-        .map(|line| {
-            line.split_whitespace()
-                .map(|l| l.parse::<u32>().expect("parsing level as int"))
-                .collect::<Vec<_>>()
+// The Problem Dampener tolerates removing a single level from an unsafe report, or up to
+// `removals` levels when configured.
+fn part2(input: Input, removals: usize, bounds: &RangeInclusive<u32>, explain: bool) -> Result<()> {
+    let mut safe_count = 0;
+    let dampened = parallel::map(reports(&input)?, |levels| {
+        levels.map(|levels| {
+            let removed = dampen(&levels, removals, bounds);
+            (levels, removed)
         })
-        .filter_map(|levels| {
-            let mut safe = is_safe(&levels);
-            if !safe {
-                for i in 0..levels.len() {
-                    let levels: Vec<_> = levels
-                        .iter()
-                        .enumerate()
-                        .filter(|(n, _)| *n != i)
-                        .map(|(_, v)| *v)
-                        .collect();
-                    safe = is_safe(&levels);
-                    if safe {
-                        break;
-                    }
+    });
+    for dampened in dampened {
+        let (levels, removed) = dampened?;
+        if explain {
+            match &removed {
+                None => eprintln!("{levels:?}: unsafe"),
+                Some(removed) if removed.is_empty() => eprintln!("{levels:?}: safe"),
+                Some(removed) => {
+                    let removed: Vec<_> = removed.iter().map(|i| format!("{} (index {i})", levels[*i])).collect();
+                    eprintln!("{levels:?}: safe by removing {}", removed.join(", "));
                 }
             }
-            safe.then_some(1)
-        })
-        .sum();
+        }
+        if removed.is_some() {
+            safe_count += 1;
+        }
+    }
     println!("{safe_count}");
 
     Ok(())
 }
 
+// Reports are parsed one line at a time, so only the current one is ever held in memory (unless
+// the `parallel` feature collects them all).
+fn reports(input: &Input) -> Result<impl Iterator<Item = Result<Vec<u32>>>> {
+    input.parse_lines(|line| {
+        line.split_whitespace()
+            .map(|l| l.parse::<u32>().context("parsing level as int"))
+            .collect()
+    })
+}
+
 // For a report to be safe, it must satisfy the following rules:
 // - The levels are either all increasing or all decreasing.
-// - Any two adjacent levels differ by at least one and at most three.
-fn is_safe(levels: &[u32]) -> bool {
-    let mut ordering = None;
-    for (left, right) in levels.iter().zip(levels.iter().skip(1)) {
-        match (left.cmp(right), ordering) {
-            (Ordering::Equal, _) => return false,
-            (new_ordering, Some(ord)) if ord != new_ordering => return false,
-            (new_ordering, current_ordering) => match left.abs_diff(*right) {
-                1..=3 => {
-                    if current_ordering.is_none() {
-                        ordering = Some(new_ordering);
+// - Any two adjacent levels differ by at least one and at most three (`bounds`).
+fn is_safe(levels: &[u32], bounds: &RangeInclusive<u32>) -> bool {
+    dampen(levels, 0, bounds).is_some()
+}
+
+// Returns the indices of the fewest levels (at most `max_removals`) to remove for the report to
+// be safe, or None when that isn't possible.
+//
+// For each direction, `reachable[i][r]` tells whether a safe prefix can end by keeping level `i`
+// after removing exactly `r` of the levels before it. Level `i` can only follow one of the
+// `max_removals + 1` levels before it, so this runs in O(n * k^2): linear for a given k.
+fn dampen(levels: &[u32], max_removals: usize, bounds: &RangeInclusive<u32>) -> Option<Vec<usize>> {
+    let n = levels.len();
+    if n <= max_removals {
+        return Some((0..n).collect());
+    }
+
+    let mut best: Option<Vec<usize>> = None;
+    for increasing in [true, false] {
+        let step_ok = |left: u32, right: u32| {
+            let diff = if increasing {
+                right.checked_sub(left)
+            } else {
+                left.checked_sub(right)
+            };
+            diff.is_some_and(|d| bounds.contains(&d))
+        };
+
+        // predecessor kept level for each (i, r), None when `i` is the first kept level
+        let mut reachable: Vec<Vec<Option<Option<usize>>>> = vec![vec![None; max_removals + 1]; n];
+        for i in 0..n {
+            if i <= max_removals {
+                reachable[i][i] = Some(None);
+            }
+            for j in i.saturating_sub(max_removals + 1)..i {
+                let skipped = i - j - 1;
+                if !step_ok(levels[j], levels[i]) {
+                    continue;
+                }
+                for r in skipped..=max_removals {
+                    if reachable[i][r].is_none() && reachable[j][r - skipped].is_some() {
+                        reachable[i][r] = Some(Some(j));
                     }
                 }
-                _ => return false,
-            },
+            }
         }
+
+        // the last kept level also drops everything after it
+        let end = (n.saturating_sub(max_removals + 1)..n)
+            .flat_map(|i| (0..=max_removals).map(move |r| (i, r, r + n - 1 - i)))
+            .filter(|(i, r, total)| *total <= max_removals && reachable[*i][*r].is_some())
+            .min_by_key(|(_, _, total)| *total);
+        let Some((last, mut r, _)) = end else {
+            continue;
+        };
+
+        let mut kept = vec![false; n];
+        kept[last] = true;
+        let mut i = last;
+        while let Some(Some(j)) = reachable[i][r] {
+            r -= i - j - 1;
+            kept[j] = true;
+            i = j;
+        }
+        let removed: Vec<usize> = (0..n).filter(|i| !kept[*i]).collect();
+        if best.as_ref().is_none_or(|b| removed.len() < b.len()) {
+            best = Some(removed);
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: RangeInclusive<u32> = 1..=3;
+
+    #[test]
+    fn is_safe_example_reports() {
+        assert!(is_safe(&[7, 6, 4, 2, 1], &BOUNDS));
+        assert!(!is_safe(&[1, 2, 7, 8, 9], &BOUNDS));
+        assert!(!is_safe(&[9, 7, 6, 2, 1], &BOUNDS));
+        assert!(!is_safe(&[1, 3, 2, 4, 5], &BOUNDS));
+        assert!(!is_safe(&[8, 6, 4, 4, 1], &BOUNDS));
+        assert!(is_safe(&[1, 3, 6, 7, 9], &BOUNDS));
+    }
+
+    #[test]
+    fn is_safe_custom_bounds() {
+        assert!(is_safe(&[1, 5, 9], &(4..=4)));
+        assert!(!is_safe(&[1, 2, 6], &(2..=4)));
+        assert!(is_safe(&[5, 5, 5], &(0..=0)));
+    }
+
+    #[test]
+    fn dampen_removes_the_fewest_levels() {
+        assert_eq!(dampen(&[7, 6, 4, 2, 1], 1, &BOUNDS), Some(vec![]));
+        assert_eq!(dampen(&[1, 2, 7, 8, 9], 1, &BOUNDS), None);
+        assert_eq!(dampen(&[8, 6, 4, 4, 1], 1, &BOUNDS).map(|r| r.len()), Some(1));
+        assert_eq!(dampen(&[1, 9, 2, 9, 3], 1, &BOUNDS), None);
+        assert_eq!(dampen(&[1, 9, 2, 9, 3], 2, &BOUNDS), Some(vec![1, 3]));
     }
-    true
 }
@@ -1,12 +1,14 @@
-use std::ops::Range;
-
 use anyhow::Result;
-use regex::Regex;
+use tracing::info;
 
 #[derive(Debug, clap::Args)]
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// List each recognised instruction with its byte offset and whether it was executed, to stderr.
+    #[arg(long, global = true)]
+    trace: bool,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -21,69 +23,194 @@ pub enum Commands {
 impl Args {
     pub fn run(self, input: String) -> Result<()> {
         match self.command {
-            Commands::Part1 => part1(input),
-            Commands::Part2 => part2(input),
+            Commands::Part1 => part1(input, self.trace),
+            Commands::Part2 => part2(input, self.trace),
         }
     }
 }
 
-fn part1(input: String) -> Result<()> {
-    let sum = mul_sum(&input);
+fn part1(input: String, trace: bool) -> Result<()> {
+    let instruction_set: &[&dyn Opcode] = &[&Mul];
+    let sum = interpret(&input, instruction_set, trace);
     println!("{sum}");
 
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
-    let ranges = find_ranges(&input);
-    let input = trim_input(input.clone(), ranges);
-    let sum = mul_sum(&input);
+// The do() and don't() instructions enable and disable future mul instructions.
+fn part2(input: String, trace: bool) -> Result<()> {
+    let instruction_set: &[&dyn Opcode] = &[&Mul, &Do, &Dont];
+    let sum = interpret(&input, instruction_set, trace);
     println!("{sum}");
 
     Ok(())
 }
 
-fn mul_sum(input: &str) -> u64 {
-    let re_mul = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)").unwrap();
-    re_mul
-        .captures_iter(input)
-        .map(|caps| {
-            let (_, [left, right]) = caps.extract();
-            left.parse::<u64>().unwrap() * right.parse::<u64>().unwrap()
-        })
-        .sum()
+fn interpret(input: &str, instruction_set: &[&dyn Opcode], trace: bool) -> u64 {
+    let mut machine = Machine::default();
+    for token in tokenize(input, instruction_set) {
+        let executed = machine.execute(&token);
+        info!("{token} executed:{executed}");
+        if trace {
+            let status = if executed { "executed" } else { "skipped" };
+            eprintln!("{:>6} {token} {status}", token.offset);
+        }
+    }
+    machine.sum
+}
+
+#[derive(Debug)]
+struct Machine {
+    enabled: bool,
+    sum: u64,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self { enabled: true, sum: 0 }
+    }
+}
+
+impl Machine {
+    // Returns whether the instruction was executed: conditional instructions are skipped while
+    // the machine is disabled.
+    fn execute(&mut self, token: &Token) -> bool {
+        if token.opcode.conditional() && !self.enabled {
+            return false;
+        }
+        token.opcode.execute(&token.args, self);
+        true
+    }
+}
+
+// An instruction looks like a function call: `name(args)`.
+trait Opcode {
+    fn name(&self) -> &'static str;
+
+    // Longest raw text between the parentheses that can be valid, so that the closing one is
+    // only looked for that far.
+    fn max_args_len(&self) -> usize {
+        0
+    }
+
+    // Validate and decode the raw text between the parentheses.
+    fn parse_args(&self, args: &str) -> Option<Vec<u64>>;
+
+    fn execute(&self, args: &[u64], machine: &mut Machine);
+
+    // Whether the instruction is subject to the machine being enabled.
+    fn conditional(&self) -> bool {
+        false
+    }
 }
 
-fn trim_input(input: String, ranges: Vec<Range<usize>>) -> String {
-    let mut input = input;
+struct Mul;
+
+impl Opcode for Mul {
+    fn name(&self) -> &'static str {
+        "mul"
+    }
+
+    fn max_args_len(&self) -> usize {
+        "XXX,YYY".len()
+    }
+
+    // mul(X,Y), where X and Y are each 1-3 digit numbers.
+    fn parse_args(&self, args: &str) -> Option<Vec<u64>> {
+        let (left, right) = args.split_once(',')?;
+        [left, right]
+            .iter()
+            .map(|n| match n.len() {
+                1..=3 if n.bytes().all(|b| b.is_ascii_digit()) => n.parse().ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn execute(&self, args: &[u64], machine: &mut Machine) {
+        machine.sum += args[0] * args[1];
+    }
+
+    fn conditional(&self) -> bool {
+        true
+    }
+}
+
+struct Do;
+
+impl Opcode for Do {
+    fn name(&self) -> &'static str {
+        "do"
+    }
+
+    fn parse_args(&self, args: &str) -> Option<Vec<u64>> {
+        args.is_empty().then(Vec::new)
+    }
+
+    fn execute(&self, _args: &[u64], machine: &mut Machine) {
+        machine.enabled = true;
+    }
+}
+
+struct Dont;
+
+impl Opcode for Dont {
+    fn name(&self) -> &'static str {
+        "don't"
+    }
+
+    fn parse_args(&self, args: &str) -> Option<Vec<u64>> {
+        args.is_empty().then(Vec::new)
+    }
+
+    fn execute(&self, _args: &[u64], machine: &mut Machine) {
+        machine.enabled = false;
+    }
+}
+
+struct Token<'a> {
+    offset: usize,
+    opcode: &'a dyn Opcode,
+    args: Vec<u64>,
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let args: Vec<_> = self.args.iter().map(u64::to_string).collect();
+        write!(f, "{}({})", self.opcode.name(), args.join(","))
+    }
+}
+
+// Scan the corrupted memory once, recognising any instruction of the set at every offset.
+// Everything else is noise. Arguments are bounded in length, which keeps the scan linear however
+// many instructions are left unterminated.
+fn tokenize<'a>(input: &str, instruction_set: &[&'a dyn Opcode]) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
     let mut offset = 0;
-    for r in ranges {
-        let (start, end) = (r.start - offset, r.end - offset);
-        input.replace_range(start..end, "");
-        offset += r.len()
-    }
-    input
-}
-
-fn find_ranges(input: &str) -> Vec<Range<usize>> {
-    let re_dontdo = Regex::new(r"don't\(\)|do\(\)").unwrap();
-    let mut ranges: Vec<Range<usize>> = Vec::new();
-    let mut current_start = None;
-    for m in re_dontdo.find_iter(input) {
-        match m.len() {
-            // dont
-            7 => _ = current_start.get_or_insert(m.start()),
-            // do()
-            4 => {
-                if let Some(start) = current_start.take() {
-                    ranges.push(start..m.end());
-                }
+    'scan: while offset < input.len() {
+        let rest = &input.as_bytes()[offset..];
+        for opcode in instruction_set {
+            let name = opcode.name().as_bytes();
+            if !rest.starts_with(name) || rest.get(name.len()) != Some(&b'(') {
+                continue;
             }
-            _ => {}
+            let args_start = offset + name.len() + 1;
+            let args_end = input.len().min(args_start + opcode.max_args_len() + 1);
+            let Some(args_len) = input.as_bytes()[args_start..args_end].iter().position(|b| *b == b')') else {
+                continue;
+            };
+            let Some(args) = opcode.parse_args(&input[args_start..args_start + args_len]) else {
+                continue;
+            };
+            tokens.push(Token {
+                offset,
+                opcode: *opcode,
+                args,
+            });
+            offset = args_start + args_len + 1;
+            continue 'scan;
         }
+        offset += 1;
     }
-    if let Some(start) = current_start.take() {
-        ranges.push(start..input.len());
-    }
-    ranges
+    tokens
 }
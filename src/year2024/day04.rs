@@ -1,3 +1,6 @@
+use std::str::FromStr;
+
+use adventofcode::word_search::{Grid, Pattern};
 use anyhow::Result;
 use tracing::info;
 
@@ -5,12 +8,20 @@ use tracing::info;
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Print every match with its position and direction (or rotation) to stderr, ahead of the count.
+    #[arg(long, global = true)]
+    list: bool,
 }
 
 #[derive(Debug, clap::Subcommand)]
 pub enum Commands {
     /// Part 1.
-    Part1,
+    Part1 {
+        /// Words to look for.
+        #[arg(long, value_delimiter = ',', default_value = "XMAS")]
+        words: Vec<String>,
+    },
 
     /// Part 2.
     Part2,
@@ -19,148 +30,47 @@ pub enum Commands {
 impl Args {
     pub fn run(self, input: String) -> Result<()> {
         match self.command {
-            Commands::Part1 => part1(input),
-            Commands::Part2 => part2(input),
+            Commands::Part1 { words } => part1(input, &words, self.list),
+            Commands::Part2 => part2(input, self.list),
         }
     }
 }
 
-// We know the input is ASCII, therefore we can index the string using its bytes representation.
-fn part1(input: String) -> Result<()> {
-    let mut x_hor = 0;
-    let mut x_ver = 0;
-    let mut x_dial = 0;
-    let mut x_diar = 0;
-    let mut s_hor = 0;
-    let mut s_ver = 0;
-    let mut s_dial = 0;
-    let mut s_diar = 0;
+// Words can be horizontal, vertical, diagonal, written backwards, or even overlapping other words.
+fn part1(input: String, words: &[String], list: bool) -> Result<()> {
+    let grid = Grid::from_str(&input)?;
+    info!("width:{} height:{}", grid.width(), grid.height());
 
-    let mut count = 0;
-    let lines: Vec<_> = input.lines().collect();
-    for y in 0..lines.len() {
-        let line = lines[y];
-        for x in 0..line.len() {
-            match &line[x..x + 1] {
-                // check for XMAS
-                "X" => {
-                    // horizontal
-                    if x < line.len() - 3 && &line[x + 1..x + 4] == "MAS" {
-                        count += 1;
-                        x_hor += 1;
-                    }
-                    // vertical
-                    if y < lines.len() - 3
-                        && (
-                            &lines[y + 1][x..x + 1],
-                            &lines[y + 2][x..x + 1],
-                            &lines[y + 3][x..x + 1],
-                        ) == ("M", "A", "S")
-                    {
-                        count += 1;
-                        x_ver += 1;
-                    }
-                    // diagonal left \
-                    if x < line.len() - 3
-                        && y < lines.len() - 3
-                        && (
-                            &lines[y + 1][x + 1..x + 2],
-                            &lines[y + 2][x + 2..x + 3],
-                            &lines[y + 3][x + 3..x + 4],
-                        ) == ("M", "A", "S")
-                    {
-                        count += 1;
-                        x_dial += 1;
-                    }
-                    // diagonal right /
-                    if x > 2
-                        && y < lines.len() - 3
-                        && (
-                            &lines[y + 1][x - 1..x],
-                            &lines[y + 2][x - 2..x - 1],
-                            &lines[y + 3][x - 3..x - 2],
-                        ) == ("M", "A", "S")
-                    {
-                        count += 1;
-                        x_diar += 1;
-                    }
-                }
-                // check for SAMX
-                "S" => {
-                    // horizontal
-                    if x < line.len() - 3 && &line[x + 1..x + 4] == "AMX" {
-                        count += 1;
-                        s_hor += 1;
-                    }
-                    // vertical
-                    if y < lines.len() - 3
-                        && (
-                            &lines[y + 1][x..x + 1],
-                            &lines[y + 2][x..x + 1],
-                            &lines[y + 3][x..x + 1],
-                        ) == ("A", "M", "X")
-                    {
-                        count += 1;
-                        s_ver += 1;
-                    }
-                    // diagonal left \
-                    if x < line.len() - 3
-                        && y < lines.len() - 3
-                        && (
-                            &lines[y + 1][x + 1..x + 2],
-                            &lines[y + 2][x + 2..x + 3],
-                            &lines[y + 3][x + 3..x + 4],
-                        ) == ("A", "M", "X")
-                    {
-                        count += 1;
-                        s_dial += 1;
-                    }
-                    // diagonal right /
-                    if x > 2
-                        && y < lines.len() - 3
-                        && (
-                            &lines[y + 1][x - 1..x],
-                            &lines[y + 2][x - 2..x - 1],
-                            &lines[y + 3][x - 3..x - 2],
-                        ) == ("A", "M", "X")
-                    {
-                        count += 1;
-                        s_diar += 1;
-                    }
-                }
-                _ => {}
-            }
+    let matches = grid.find_words(words);
+    if list {
+        for m in matches.iter() {
+            eprintln!("{} {:?} {}", words[m.word], m.pos, m.direction);
         }
     }
-    info!(
-        "X(hor:{x_hor} ver:{x_ver} dial:{x_dial} diar:{x_diar}) S(hor:{s_hor} ver:{s_ver} dial:{s_dial} diar:{s_diar})"
-    );
-    println!("{count}");
 
+    println!("{}", matches.len());
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
-    let mut count = 0;
-    let lines: Vec<_> = input.lines().collect();
-    for y in 0..lines.len() - 2 {
-        for x in 0..lines[y].len() - 2 {
-            match (
-                &lines[y][x..x + 1],
-                &lines[y][x + 2..x + 3],
-                &lines[y + 1][x + 1..x + 2],
-                &lines[y + 2][x..x + 1],
-                &lines[y + 2][x + 2..x + 3],
-            ) {
-                ("M", "M", "A", "S", "S")
-                | ("S", "S", "A", "M", "M")
-                | ("S", "M", "A", "S", "M")
-                | ("M", "S", "A", "M", "S") => count += 1,
-                _ => {}
-            }
+// Two MAS in the shape of an X, each of which can be written forwards or backwards.
+const X_MAS: &str = "
+M.S
+.A.
+M.S
+";
+
+fn part2(input: String, list: bool) -> Result<()> {
+    let grid = Grid::from_str(&input)?;
+    info!("width:{} height:{}", grid.width(), grid.height());
+
+    let pattern = Pattern::from_str(X_MAS)?;
+    let matches = grid.find_pattern(&pattern);
+    if list {
+        for m in matches.iter() {
+            eprintln!("{:?} rotation:{}", m.pos, m.rotation);
         }
     }
-    println!("{count}");
 
+    println!("{}", matches.len());
     Ok(())
 }
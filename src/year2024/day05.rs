@@ -1,11 +1,22 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 
 #[derive(Debug, clap::Args)]
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Write the pages and ordering rules to this Graphviz file.
+    #[arg(long, global = true)]
+    dot: Option<PathBuf>,
+
+    /// Only graph the pages of this update (the first one being 1), with the rules it breaks
+    /// highlighted.
+    #[arg(long, global = true, requires = "dot")]
+    update: Option<usize>,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -19,6 +30,9 @@ pub enum Commands {
 
 impl Args {
     pub fn run(self, input: String) -> Result<()> {
+        if let Some(path) = &self.dot {
+            write_dot(&input, self.update, path)?;
+        }
         match self.command {
             Commands::Part1 => part1(input),
             Commands::Part2 => part2(input),
@@ -26,7 +40,9 @@ impl Args {
     }
 }
 
-fn part1(input: String) -> Result<()> {
+type Rules = Vec<(u32, u32)>;
+
+fn parse(input: &str) -> (Rules, Vec<Vec<u32>>) {
     let rule_pairs: Vec<_> = input
         .lines()
         .filter(|l| l.contains('|'))
@@ -48,6 +64,11 @@ fn part1(input: String) -> Result<()> {
                 .collect::<Vec<_>>()
         })
         .collect();
+    (rule_pairs, updates)
+}
+
+fn part1(input: String) -> Result<()> {
+    let (rule_pairs, updates) = parse(&input);
 
     let mapping_right_left: HashMap<u32, Vec<_>> = rule_pairs.iter().fold(HashMap::new(), |mut acc, p| {
         acc.entry(p.1).or_default().push(p.0);
@@ -85,27 +106,7 @@ fn part1(input: String) -> Result<()> {
 }
 
 fn part2(input: String) -> Result<()> {
-    let rule_pairs: Vec<_> = input
-        .lines()
-        .filter(|l| l.contains('|'))
-        .map(|l| {
-            let mut split = l.splitn(2, '|');
-            (
-                split.next().unwrap().parse::<u32>().unwrap(),
-                split.next().unwrap().parse::<u32>().unwrap(),
-            )
-        })
-        .collect();
-    let updates: Vec<_> = input
-        .lines()
-        .filter(|l| l.contains(','))
-        .map(|l| {
-            l.trim()
-                .split(",")
-                .map(|n| n.parse::<u32>().unwrap())
-                .collect::<Vec<_>>()
-        })
-        .collect();
+    let (rule_pairs, updates) = parse(&input);
 
     let mapping_right_left: HashMap<u32, Vec<_>> = rule_pairs.iter().fold(HashMap::new(), |mut acc, p| {
         acc.entry(p.1).or_default().push(p.0);
@@ -150,3 +151,47 @@ fn part2(input: String) -> Result<()> {
 
     Ok(())
 }
+
+// Pages are nodes and rules `X|Y` are edges `X -> Y`. For an update, only its pages and the rules
+// between them are kept, and a rule is broken when Y is printed before X.
+fn write_dot(input: &str, update: Option<usize>, path: &Path) -> Result<()> {
+    let (rule_pairs, updates) = parse(input);
+    let update = match update {
+        Some(n) => Some(
+            updates
+                .get(n.wrapping_sub(1))
+                .ok_or_else(|| anyhow!("no update {n}, there are {} updates", updates.len()))?,
+        ),
+        None => None,
+    };
+    let position = |page: u32| update.and_then(|up| up.iter().position(|p| *p == page));
+
+    let mut out = String::new();
+    writeln!(out, "digraph rules {{").unwrap();
+    writeln!(out, "  node [shape=circle];").unwrap();
+    match update {
+        Some(up) => {
+            for (i, page) in up.iter().enumerate() {
+                writeln!(out, r#"  {page} [label="{page}\n#{}"];"#, i + 1).unwrap();
+            }
+        }
+        None => {
+            let pages: BTreeSet<u32> = rule_pairs.iter().flat_map(|(x, y)| [*x, *y]).collect();
+            for page in pages {
+                writeln!(out, "  {page};").unwrap();
+            }
+        }
+    }
+    for (x, y) in rule_pairs.iter() {
+        let style = match (update, position(*x), position(*y)) {
+            (None, _, _) => "",
+            (Some(_), Some(px), Some(py)) if py < px => " [color=red, fontcolor=red, penwidth=2, label=broken]",
+            (Some(_), Some(_), Some(_)) => "",
+            (Some(_), _, _) => continue,
+        };
+        writeln!(out, "  {x} -> {y}{style};").unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+
+    std::fs::write(path, out).with_context(|| format!("writing dot file '{}'", path.display()))
+}
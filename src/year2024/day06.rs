@@ -1,3 +1,9 @@
+use std::path::{Path, PathBuf};
+
+use adventofcode::animation::{AnimationArgs, Recorder};
+use adventofcode::cancel;
+use adventofcode::parallel;
+use adventofcode::render::{self, Image};
 use anyhow::Result;
 use tracing::info;
 
@@ -5,6 +11,13 @@ use tracing::info;
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Save an image of the guard's patrol to this file (.png or .ppm).
+    #[arg(long, global = true)]
+    render: Option<PathBuf>,
+
+    #[command(flatten)]
+    animation: AnimationArgs,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -19,8 +32,8 @@ pub enum Commands {
 impl Args {
     pub fn run(self, input: String) -> Result<()> {
         match self.command {
-            Commands::Part1 => part1(input),
-            Commands::Part2 => part2(input),
+            Commands::Part1 => part1(input, self.render.as_deref(), &self.animation),
+            Commands::Part2 => part2(input, self.render.as_deref(), &self.animation),
         }
     }
 }
@@ -49,6 +62,24 @@ impl Direction {
             Direction::Right => Direction::Down,
         }
     }
+
+    fn vector(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -108,50 +139,31 @@ impl Map {
         (map, guard)
     }
 
-    fn lookup_visited(&self, pos: (usize, usize), dir: Direction) -> Option<(usize, usize)> {
-        let mut pos = (pos.0 as isize, pos.1 as isize);
+    // Walk a copy of the map with one more obstruction, until the guard leaves (false) or comes
+    // back to a position it was in, facing the same way (true). Doesn't walk at all when cancelled,
+    // a single walk being short enough to finish.
+    fn loops_with(&self, guard: &Guard, obstruction: (usize, usize)) -> bool {
+        if cancel::is_cancelled() {
+            return false;
+        }
+        let mut seen = vec![[false; 4]; self.width * self.height];
+        let (mut pos, mut dir) = (guard.pos, guard.dir.clone());
         loop {
-            let new_pos = match dir {
-                Direction::Up => (pos.0, pos.1 - 1),
-                Direction::Down => (pos.0, pos.1 + 1),
-                Direction::Left => (pos.0 - 1, pos.1),
-                Direction::Right => (pos.0 + 1, pos.1),
-            };
-            info!("lookup_visited: {pos:?} -> {new_pos:?}");
-            // check if out of bounds
-            if new_pos.0 < 0 || new_pos.0 as usize >= self.width || new_pos.1 < 0 || new_pos.1 as usize >= self.height {
-                info!("out of bounds");
-                return None;
+            let state = &mut seen[pos.1 * self.width + pos.0][dir.index()];
+            if *state {
+                return true;
             }
-            pos = new_pos;
-            // move to new pos
-            let new_pos = (new_pos.0 as usize, new_pos.1 as usize);
-            match &self.grid[new_pos.1][new_pos.0] {
-                Position::Empty => continue,
-                Position::Obstruction => return None,
-                Position::Visited(v_dir) => {
-                    if v_dir == &dir {
-                        // try to check if obstruction in front as this is
-                        // our indicator that it should loop
-                        let front_pos = match dir {
-                            Direction::Up => (pos.0, pos.1 - 1),
-                            Direction::Down => (pos.0, pos.1 + 1),
-                            Direction::Left => (pos.0 - 1, pos.1),
-                            Direction::Right => (pos.0 + 1, pos.1),
-                        };
-                        if let Position::Obstruction = &self.grid[front_pos.1 as usize][front_pos.0 as usize] {
-                            // check if obstruction in front
-                            return Some(new_pos);
-                        }
-                        // return Some(new_pos);
-                    }
-                    // try ignoring different directions as this
-                    // might be before turning right.
-                    // alternatively, we could check if there is
-                    // an obstruction in front instead.
-                    //return None;
-                    continue;
-                }
+            *state = true;
+            let (dx, dy) = dir.vector();
+            let (x, y) = (pos.0 as isize + dx, pos.1 as isize + dy);
+            if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+                return false;
+            }
+            let next = (x as usize, y as usize);
+            if next == obstruction || self.grid[next.1][next.0] == Position::Obstruction {
+                dir = dir.look_right();
+            } else {
+                pos = next;
             }
         }
     }
@@ -178,11 +190,26 @@ impl Map {
         }
     }
 
-    fn guard_iter(&mut self, guard: &Guard) -> GuardIter {
+    // Visited positions, with the guard's starting position and the given obstructions on top.
+    fn image(&self, guard: &Guard, obstructions: &[(usize, usize)]) -> Image {
+        let mut image = Image::from_grid(&self.grid, |pos| match pos {
+            Position::Empty => render::BACKGROUND,
+            Position::Obstruction => render::WALL,
+            Position::Visited(_) => render::VISITED,
+        });
+        for obs in obstructions {
+            image.set(*obs, render::HIGHLIGHT);
+        }
+        image.set(guard.pos, render::ACTOR);
+        image
+    }
+
+    fn guard_iter<'a>(&'a mut self, guard: &Guard, recorder: &'a mut Recorder) -> GuardIter<'a> {
         GuardIter {
             map: self,
             guard: guard.clone(),
             is_orignal_position: true,
+            recorder,
         }
     }
 }
@@ -191,25 +218,27 @@ struct GuardIter<'a> {
     map: &'a mut Map,
     guard: Guard,
     is_orignal_position: bool,
+    recorder: &'a mut Recorder,
 }
 
 struct GuardWalkInsight {
     new_visit: bool,
-    found_obs_causing_loop: Option<(usize, usize)>,
 }
 
 impl Iterator for GuardIter<'_> {
     type Item = GuardWalkInsight;
 
+    // Stops early when cancelled, callers must check for cancellation before using the results.
     fn next(&mut self) -> Option<Self::Item> {
+        if cancel::is_cancelled() {
+            return None;
+        }
         let cur_pos = self.guard.pos;
         // account for original position and return guard pos right away
         if self.is_orignal_position {
             self.is_orignal_position = false;
-            return Some(GuardWalkInsight {
-                new_visit: true,
-                found_obs_causing_loop: None,
-            });
+            self.recorder.record(|| self.map.image(&self.guard, &[]));
+            return Some(GuardWalkInsight { new_visit: true });
         }
         let new_pos = match self.guard.dir {
             Direction::Up => (cur_pos.0 as isize, cur_pos.1 as isize - 1),
@@ -275,53 +304,58 @@ impl Iterator for GuardIter<'_> {
                 (guard, false)
             }
         };
-        // search whether adding an obstruction in front of our pos
-        // would result in a loop. If we search from our current pos
-        // to our right and find a visited pos in the same direction,
-        // we can (probably?) assume that placing an obstruction in
-        // front would result in a loop.
-        let found_obs_causing_loop = self.map.lookup_visited(guard.pos, guard.dir.look_right());
-        // map to show the position of the obstruction instead of the found visited pos
-        let found_obs_causing_loop = found_obs_causing_loop.map(|_| match guard.dir {
-            Direction::Up => (guard.pos.0, guard.pos.1 - 1),
-            Direction::Down => (guard.pos.0, guard.pos.1 + 1),
-            Direction::Left => (guard.pos.0 - 1, guard.pos.1),
-            Direction::Right => (guard.pos.0 + 1, guard.pos.1),
-        });
         info!("new guard: {guard:?}");
         self.guard = guard.clone();
-        Some(GuardWalkInsight {
-            new_visit,
-            found_obs_causing_loop,
-        })
+        self.recorder.record(|| self.map.image(&self.guard, &[]));
+        Some(GuardWalkInsight { new_visit })
     }
 }
 
-fn part1(input: String) -> Result<()> {
+fn part1(input: String, render: Option<&Path>, animation: &AnimationArgs) -> Result<()> {
     let (map, guard) = Map::new(input);
     info!("map: {map:?}");
     info!("guard: {guard:?}");
 
     let mut map = map;
-    let unique_visits = map.guard_iter(&guard).filter(|v| v.new_visit).count();
+    let mut recorder = animation.recorder()?;
+    let unique_visits = map.guard_iter(&guard, &mut recorder).filter(|v| v.new_visit).count();
+    cancel::check()?;
     info!("map: {map:?}");
 
+    recorder.finish(|| map.image(&guard, &[]))?;
+
     println!("{}", unique_visits);
 
+    if let Some(path) = render {
+        map.image(&guard, &[]).save(path)?;
+    }
+
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
+fn part2(input: String, render: Option<&Path>, animation: &AnimationArgs) -> Result<()> {
     let (map, guard) = Map::new(input);
     info!("map: {map:?}");
     info!("guard: {guard:?}");
 
-    let mut map = map;
-    let obs_causing_loop_count: Vec<_> = map
-        .guard_iter(&guard)
-        .filter_map(|v| v.found_obs_causing_loop)
+    // The guard can only be diverted by an obstruction placed on its patrol, but not where it
+    // stands at the start. Each candidate is then tried on the untouched map.
+    let mut patrol = map.clone();
+    let mut recorder = animation.recorder()?;
+    patrol.guard_iter(&guard, &mut recorder).for_each(drop);
+    cancel::check()?;
+    let candidates = (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| matches!(patrol.grid[y][x], Position::Visited(_)) && (x, y) != guard.pos);
+    let obs_causing_loop_count: Vec<_> = parallel::map(candidates, |obs| map.loops_with(&guard, obs).then_some(obs))
+        .flatten()
         .inspect(|v| info!("found_obs_causing_loop: {v:?}"))
         .collect();
+    cancel::check()?;
+    let map = patrol;
+
+    recorder.finish(|| map.image(&guard, &obs_causing_loop_count))?;
+
     println!("{}", obs_causing_loop_count.len());
 
     // print the map
@@ -341,5 +375,9 @@ fn part2(input: String) -> Result<()> {
     };
     map.print(&print_fn);
 
+    if let Some(path) = render {
+        map.image(&guard, &obs_causing_loop_count).save(path)?;
+    }
+
     Ok(())
 }
@@ -1,3 +1,6 @@
+use adventofcode::cancel;
+use adventofcode::input::Input;
+use adventofcode::parallel;
 use itertools::Itertools;
 
 use anyhow::{Context, Result};
@@ -19,7 +22,7 @@ pub enum Commands {
 }
 
 impl Args {
-    pub fn run(self, input: String) -> Result<()> {
+    pub fn run(self, input: Input) -> Result<()> {
         match self.command {
             Commands::Part1 => part1(input),
             Commands::Part2 => part2(input),
@@ -34,74 +37,87 @@ enum Operation {
     Concat,
 }
 
-fn part1(input: String) -> Result<()> {
+fn part1(input: Input) -> Result<()> {
     let ops = [Operation::Add, Operation::Multiply];
     let result = compute(input, &ops)?;
     println!("{result}");
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
+fn part2(input: Input) -> Result<()> {
     let ops = [Operation::Add, Operation::Multiply, Operation::Concat];
     let result = compute(input, &ops)?;
     println!("{result}");
     Ok(())
 }
 
-fn compute(input: String, ops: &[Operation]) -> Result<u64> {
+fn compute(input: Input, ops: &[Operation]) -> Result<u64> {
     let mut result = 0;
-    for line in input.lines() {
-        let Some((test_val, rem)) = line.split_once(':') else {
-            anyhow::bail!("invalid input line format: must contain ':'");
-        };
-        let test_val = test_val.parse::<u64>().context("parsing test value as integer")?;
-        let nums = {
-            let nums: std::result::Result<Vec<u64>, std::num::ParseIntError> =
-                rem.split_whitespace().map(str::parse::<u64>).collect();
-            nums.context("parse equation numbers as integers")?
-        };
+    for calibration in parallel::map(input.parse_lines(parse_equation)?, |equation| {
+        let (test_val, nums) = equation?;
+        calibrate(test_val, &nums, ops)
+    }) {
+        result += calibration?;
+    }
+    Ok(result)
+}
 
-        let ops_matrix: Vec<_> = itertools::repeat_n(ops, nums.len() - 1)
-            .multi_cartesian_product()
-            .collect();
-        info!("ops_matrix:{ops_matrix:?} (vals:{nums:?})");
-        for matrix in ops_matrix.iter() {
-            let candidate: u64 =
-                nums.iter()
-                    .tuple_windows()
-                    .enumerate()
-                    .fold(0, |acc, (idx, (l, r))| match matrix[idx] {
-                        Operation::Add => {
-                            if acc == 0 {
-                                acc + (l + r)
-                            } else {
-                                acc + r
-                            }
-                        }
-                        Operation::Multiply => {
-                            if acc == 0 {
-                                acc + (l * r)
-                            } else {
-                                acc * r
-                            }
-                        }
-                        Operation::Concat => {
-                            let digits = (*r as f64).log10().floor() as u32 + 1;
-                            if acc == 0 {
-                                acc + ((l * (10_u64.pow(digits))) + r)
-                            } else {
-                                (acc * (10_u64.pow(digits))) + r
-                            }
-                        }
-                    });
-            if candidate == test_val {
-                info!("test_val:{test_val} == candidate:{candidate}");
-                result += candidate;
-                break;
-            }
-            info!("test_val:{test_val} != candidate:{candidate}");
+// An equation line is the test value, then the numbers: `190: 10 19`.
+fn parse_equation(line: &str) -> Result<(u64, Vec<u64>)> {
+    let Some((test_val, rem)) = line.split_once(':') else {
+        anyhow::bail!("invalid input line format: must contain ':'");
+    };
+    let test_val = test_val.parse::<u64>().context("parsing test value as integer")?;
+    let nums = {
+        let nums: std::result::Result<Vec<u64>, std::num::ParseIntError> =
+            rem.split_whitespace().map(str::parse::<u64>).collect();
+        nums.context("parse equation numbers as integers")?
+    };
+    Ok((test_val, nums))
+}
+
+// The test value if some combination of `ops` produces it from the numbers, 0 otherwise.
+fn calibrate(test_val: u64, nums: &[u64], ops: &[Operation]) -> Result<u64> {
+    let ops_matrix: Vec<_> = itertools::repeat_n(ops, nums.len() - 1)
+        .multi_cartesian_product()
+        .collect();
+    info!("ops_matrix:{ops_matrix:?} (vals:{nums:?})");
+    for matrix in ops_matrix.iter() {
+        cancel::check()?;
+        let candidate: u64 = nums
+            .iter()
+            .tuple_windows()
+            .enumerate()
+            .fold(0, |acc, (idx, (l, r))| match matrix[idx] {
+                Operation::Add => {
+                    if acc == 0 {
+                        acc + (l + r)
+                    } else {
+                        acc + r
+                    }
+                }
+                Operation::Multiply => {
+                    if acc == 0 {
+                        acc + (l * r)
+                    } else {
+                        acc * r
+                    }
+                }
+                Operation::Concat => {
+                    let digits = (*r as f64).log10().floor() as u32 + 1;
+                    if acc == 0 {
+                        acc + ((l * (10_u64.pow(digits))) + r)
+                    } else {
+                        (acc * (10_u64.pow(digits))) + r
+                    }
+                }
+            });
+        if candidate == test_val {
+            info!("test_val:{test_val} == candidate:{candidate}");
+            return Ok(candidate);
         }
+        info!("test_val:{test_val} != candidate:{candidate}");
     }
 
-    Ok(result)
+    Ok(0)
 }
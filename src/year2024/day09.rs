@@ -1,7 +1,8 @@
 use std::ops::{Range, Rem};
 
+use adventofcode::cancel;
 use anyhow::Result;
-use tracing::info;
+use tracing::{info, instrument};
 
 #[derive(Debug, clap::Args)]
 pub struct Args {
@@ -28,23 +29,16 @@ impl Args {
 }
 
 fn part1(input: String) -> Result<()> {
-    let diskmap: Vec<String> = input
-        .trim()
-        .chars()
-        .enumerate()
-        .flat_map(|(i, c)| {
-            let is_disk = i.rem(2) == 0;
-            let element = if is_disk {
-                (i / 2).to_string()
-            } else {
-                String::from(".")
-            };
-            let n = c.to_digit(10).unwrap();
-            itertools::repeat_n(element, n as usize)
-        })
-        .collect();
-    info!("diskmap: {} (len:{})", diskmap.join(""), diskmap.len());
+    let diskmap = compact_blocks(expand(&input));
+    let cheksum = checksum(&diskmap);
 
+    println!("{cheksum}");
+    Ok(())
+}
+
+// Move blocks one at a time from the end of the disk to the leftmost free block.
+#[instrument(skip_all)]
+fn compact_blocks(diskmap: Vec<String>) -> Vec<String> {
     let disk_candidates: Vec<_> = diskmap
         .iter()
         .enumerate()
@@ -69,37 +63,23 @@ fn part1(input: String) -> Result<()> {
         diskmap.swap(swap.0, swap.1);
     }
     info!("new diskmap: {}", diskmap.join(""));
+    diskmap
+}
 
-    let cheksum = diskmap
-        .iter()
-        .enumerate()
-        .filter(|(_, c)| *c != ".")
-        .fold(0, |acc, (i, c)| acc + (i * c.parse::<usize>().unwrap()));
+fn part2(input: String) -> Result<()> {
+    let diskmap = compact_files(expand(&input))?;
+    let cheksum = checksum(&diskmap);
 
     println!("{cheksum}");
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
-    let mut diskmap: Vec<String> = input
-        .trim()
-        .chars()
-        .enumerate()
-        .flat_map(|(i, c)| {
-            let is_disk = i.rem(2) == 0;
-            let element = if is_disk {
-                (i / 2).to_string()
-            } else {
-                String::from(".")
-            };
-            let n = c.to_digit(10).unwrap();
-            itertools::repeat_n(element, n as usize)
-        })
-        .collect();
-    info!("diskmap: {} (len:{})", diskmap.join(""), diskmap.len());
-
+// Move whole files, from the highest file ID down, to the leftmost span of free blocks that fits.
+#[instrument(skip_all)]
+fn compact_files(mut diskmap: Vec<String>) -> Result<Vec<String>> {
     let (file_ranges, _) = compute_ranges(&diskmap);
     for f_range in file_ranges.iter().rev().cloned() {
+        cancel::check()?;
         let f_start = f_range.start;
         let f_size = f_range.len();
         let (_, empty_ranges) = compute_ranges(&diskmap);
@@ -117,17 +97,41 @@ fn part2(input: String) -> Result<()> {
         }
     }
     info!("diskmap: {} (len:{})", diskmap.join(""), diskmap.len());
+    Ok(diskmap)
+}
+
+// One block per element: the file ID, or `.` for free space.
+#[instrument(skip_all)]
+fn expand(input: &str) -> Vec<String> {
+    let diskmap: Vec<String> = input
+        .trim()
+        .chars()
+        .enumerate()
+        .flat_map(|(i, c)| {
+            let is_disk = i.rem(2) == 0;
+            let element = if is_disk {
+                (i / 2).to_string()
+            } else {
+                String::from(".")
+            };
+            let n = c.to_digit(10).unwrap();
+            itertools::repeat_n(element, n as usize)
+        })
+        .collect();
+    info!("diskmap: {} (len:{})", diskmap.join(""), diskmap.len());
+    diskmap
+}
 
-    let cheksum = diskmap
+#[instrument(skip_all)]
+fn checksum(diskmap: &[String]) -> usize {
+    diskmap
         .iter()
         .enumerate()
         .filter(|(_, c)| *c != ".")
-        .fold(0, |acc, (i, c)| acc + (i * c.parse::<usize>().unwrap()));
-
-    println!("{cheksum}");
-    Ok(())
+        .fold(0, |acc, (i, c)| acc + (i * c.parse::<usize>().unwrap()))
 }
 
+#[instrument(level = "debug", skip_all)]
 fn compute_ranges(diskmap: &[String]) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
     let mut file_ranges = Vec::new();
     let mut empty_ranges = Vec::new();
@@ -162,3 +166,25 @@ fn compute_ranges(diskmap: &[String]) -> (Vec<Range<usize>>, Vec<Range<usize>>)
 
     (file_ranges, empty_ranges)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_ranges_splits_files_and_free_space() {
+        // 12345
+        let diskmap: Vec<String> = "0..111....22222".chars().map(String::from).collect();
+        let (files, empty) = compute_ranges(&diskmap);
+        assert_eq!(files, vec![0..1, 3..6, 10..15]);
+        assert_eq!(empty, vec![1..3, 6..10]);
+    }
+
+    #[test]
+    fn compute_ranges_separates_adjacent_files() {
+        let diskmap: Vec<String> = ["0", "0", "1", ".", "2"].map(String::from).to_vec();
+        let (files, empty) = compute_ranges(&diskmap);
+        assert_eq!(files, vec![0..2, 2..3, 4..5]);
+        assert_eq!(empty, vec![3..4]);
+    }
+}
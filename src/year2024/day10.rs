@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use tracing::info;
 
 #[derive(Debug, clap::Args)]
@@ -28,123 +28,273 @@ impl Args {
 }
 
 fn part1(input: String) -> Result<()> {
-    let grid: Vec<Vec<u32>> = input
-        .lines()
-        .map(|l| l.chars().map(|c| c.to_digit(10).unwrap()).collect())
-        .collect();
-    info!("grid: {grid:?}");
-
-    let width = grid[0].len() as isize;
-    let height = grid.len() as isize;
-    let trailheads: Vec<(usize, usize)> = grid
-        .iter()
-        .enumerate()
-        .flat_map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .filter(|(_, v)| **v == 0)
-                .map(move |(x, _)| (x, y))
-        })
-        .collect();
-    info!("trailheads: {trailheads:?}");
-
-    let mut scores = Vec::new();
-    for head in trailheads.iter() {
-        let mut tails = HashSet::new();
-
-        let mut queue = VecDeque::new();
-        queue.push_back(((head.0 as isize, head.1 as isize), 1));
-
-        while let Some((pos, val)) = queue.pop_front() {
-            let directions = [
-                (pos.0 - 1, pos.1),
-                (pos.0 + 1, pos.1),
-                (pos.0, pos.1 - 1),
-                (pos.0, pos.1 + 1),
-            ];
-            for (x, y) in directions {
-                if x < 0 || x >= width {
-                    continue;
-                }
-                if y < 0 || y >= height {
-                    continue;
-                }
-                if grid[y as usize][x as usize] != val {
-                    continue;
-                }
-                if val == 9 {
-                    tails.insert((x, y));
-                    continue;
-                }
-                queue.push_front(((x, y), val + 1));
-            }
-        }
-        scores.push(tails.len());
+    let map = TopographicMap::from_str(&input)?;
+    info!("width:{} height:{}", map.width, map.height);
+
+    let summary = map.hike();
+    for t in summary.trailheads.iter() {
+        info!("trailhead:{:?} score:{}", t.pos, t.score);
     }
-    info!("scores: {scores:?}");
 
-    let sum: usize = scores.iter().sum();
+    let sum: usize = summary.trailheads.iter().map(|t| t.score).sum();
     println!("{sum}");
     Ok(())
 }
 
 fn part2(input: String) -> Result<()> {
-    let grid: Vec<Vec<u32>> = input
-        .lines()
-        .map(|l| l.chars().map(|c| c.to_digit(10).unwrap()).collect())
-        .collect();
-    info!("grid: {grid:?}");
-
-    let width = grid[0].len() as isize;
-    let height = grid.len() as isize;
-    let trailheads: Vec<(usize, usize)> = grid
-        .iter()
-        .enumerate()
-        .flat_map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .filter(|(_, v)| **v == 0)
-                .map(move |(x, _)| (x, y))
-        })
-        .collect();
-    info!("trailheads: {trailheads:?}");
-
-    let mut ratings = Vec::new();
-    for head in trailheads.iter() {
-        let mut tails = HashMap::new();
-
-        let mut queue = VecDeque::new();
-        queue.push_back(((head.0 as isize, head.1 as isize), 1));
-
-        while let Some((pos, val)) = queue.pop_front() {
-            let directions = [
-                (pos.0 - 1, pos.1),
-                (pos.0 + 1, pos.1),
-                (pos.0, pos.1 - 1),
-                (pos.0, pos.1 + 1),
-            ];
-            for (x, y) in directions {
-                if x < 0 || x >= width {
-                    continue;
-                }
-                if y < 0 || y >= height {
-                    continue;
-                }
-                if grid[y as usize][x as usize] != val {
-                    continue;
+    let map = TopographicMap::from_str(&input)?;
+    info!("width:{} height:{}", map.width, map.height);
+
+    let summary = map.hike();
+    for t in summary.trailheads.iter() {
+        info!("trailhead:{:?} rating:{}", t.pos, t.rating);
+    }
+
+    let sum: u64 = summary.trailheads.iter().map(|t| t.rating).sum();
+    println!("{sum}");
+    Ok(())
+}
+
+const PEAK: u8 = 9;
+
+#[derive(Debug)]
+struct TopographicMap {
+    heights: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+impl FromStr for TopographicMap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut heights = Vec::new();
+        let mut width = None;
+        let mut height = 0;
+        for (y, line) in s.lines().filter(|l| !l.trim().is_empty()).enumerate() {
+            let line = line.trim();
+            match width {
+                Some(w) if w != line.len() => {
+                    return Err(anyhow!(
+                        "invalid input formatting: line {y} has length {} (expected {w})",
+                        line.len()
+                    ))
                 }
-                if val == 9 {
-                    tails.entry((x, y)).and_modify(|v: &mut usize| *v += 1).or_insert(1);
-                    continue;
+                _ => width = Some(line.len()),
+            }
+            for (x, c) in line.chars().enumerate() {
+                let h = c
+                    .to_digit(10)
+                    .ok_or_else(|| anyhow!("invalid input formatting: '{c}' at ({x}, {y}) is not a height"))?;
+                heights.push(h as u8);
+            }
+            height += 1;
+        }
+        let width = width.ok_or(anyhow!("invalid input formatting: empty map"))?;
+        Ok(Self { heights, width, height })
+    }
+}
+
+#[derive(Debug)]
+struct Trailhead {
+    pos: (usize, usize),
+    // Number of distinct peaks reachable from this trailhead.
+    score: usize,
+    // Number of distinct hiking trails starting at this trailhead.
+    rating: u64,
+}
+
+#[derive(Debug)]
+struct HikeSummary {
+    trailheads: Vec<Trailhead>,
+}
+
+impl TopographicMap {
+    fn neighbours(&self, idx: usize) -> impl Iterator<Item = usize> {
+        let (x, y) = (idx % self.width, idx / self.width);
+        let width = self.width;
+        [
+            (x > 0).then(|| idx - 1),
+            (x + 1 < self.width).then(|| idx + 1),
+            (y > 0).then(|| idx - width),
+            (y + 1 < self.height).then(|| idx + width),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    // Dynamic programming pass over heights 9 -> 0.
+    //
+    // Every cell at height h can only be reached from a cell at height h-1, so once all cells at
+    // height h+1 know which peaks they lead to and how many distinct trails they start, the cells
+    // at height h are the union (resp. sum) of their uphill neighbours. Only two layers of sets
+    // are alive at any time, cells leading to no peak have none, and sets stay sparse while they
+    // are small, which keeps memory bounded on very large maps.
+    fn hike(&self) -> HikeSummary {
+        let mut layers: Vec<Vec<usize>> = vec![Vec::new(); PEAK as usize + 1];
+        for (idx, h) in self.heights.iter().enumerate() {
+            layers[*h as usize].push(idx);
+        }
+        let peaks = layers[PEAK as usize].len();
+        info!("peaks: {peaks}");
+
+        let mut reachable: Vec<Option<PeakSet>> = (0..self.heights.len()).map(|_| None).collect();
+        let mut trails: Vec<u64> = vec![0; self.heights.len()];
+
+        for (peak_id, idx) in layers[PEAK as usize].iter().enumerate() {
+            reachable[*idx] = Some(PeakSet::Sparse(vec![peak_id]));
+            trails[*idx] = 1;
+        }
+
+        for h in (0..PEAK).rev() {
+            for idx in layers[h as usize].iter().copied() {
+                let mut set: Option<PeakSet> = None;
+                let mut count = 0;
+                for n in self.neighbours(idx).filter(|n| self.heights[*n] == h + 1) {
+                    match (&mut set, &reachable[n]) {
+                        (Some(set), Some(n_set)) => set.union_with(n_set, peaks),
+                        (None, Some(n_set)) => set = Some(n_set.clone()),
+                        (_, None) => {}
+                    }
+                    count += trails[n];
                 }
-                queue.push_front(((x, y), val + 1));
+                reachable[idx] = set;
+                trails[idx] = count;
+            }
+            // the layer above is never looked at again
+            for idx in layers[h as usize + 1].iter() {
+                reachable[*idx] = None;
             }
         }
-        ratings.push(tails.values().sum());
+
+        let trailheads = layers[0]
+            .iter()
+            .map(|idx| Trailhead {
+                pos: (idx % self.width, idx / self.width),
+                score: reachable[*idx].as_ref().map(PeakSet::len).unwrap_or(0),
+                rating: trails[*idx],
+            })
+            .collect();
+        HikeSummary { trailheads }
     }
-    info!("rating: {ratings:?}");
+}
 
-    let sum: usize = ratings.iter().sum();
-    println!("{sum}");
-    Ok(())
+// Peaks reachable from a cell. Most cells only lead to a handful of peaks, so the set is a sorted
+// list of peak ids until a bitset over all the peaks gets smaller.
+#[derive(Debug, Clone)]
+enum PeakSet {
+    Sparse(Vec<usize>),
+    Dense(BitSet),
+}
+
+impl PeakSet {
+    fn union_with(&mut self, other: &PeakSet, peaks: usize) {
+        if let (PeakSet::Sparse(l), PeakSet::Sparse(r)) = (&mut *self, other) {
+            *l = merge(l, r);
+            // the list takes a word per peak, the bitset a bit per peak
+            if l.len() * usize::BITS as usize > peaks {
+                self.make_dense(peaks);
+            }
+            return;
+        }
+        let dense = self.make_dense(peaks);
+        match other {
+            PeakSet::Sparse(r) => r.iter().for_each(|p| dense.insert(*p)),
+            PeakSet::Dense(r) => dense.union_with(r),
+        }
+    }
+
+    fn make_dense(&mut self, peaks: usize) -> &mut BitSet {
+        if let PeakSet::Sparse(l) = self {
+            let mut set = BitSet::new(peaks);
+            l.iter().for_each(|p| set.insert(*p));
+            *self = PeakSet::Dense(set);
+        }
+        match self {
+            PeakSet::Dense(set) => set,
+            PeakSet::Sparse(_) => unreachable!(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            PeakSet::Sparse(l) => l.len(),
+            PeakSet::Dense(set) => set.len(),
+        }
+    }
+}
+
+// Union of two sorted lists without duplicates.
+fn merge(l: &[usize], r: &[usize]) -> Vec<usize> {
+    let mut merged = Vec::with_capacity(l.len() + r.len());
+    let (mut i, mut j) = (0, 0);
+    while i < l.len() && j < r.len() {
+        match l[i].cmp(&r[j]) {
+            std::cmp::Ordering::Less => {
+                merged.push(l[i]);
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                merged.push(r[j]);
+                j += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                merged.push(l[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    merged.extend_from_slice(&l[i..]);
+    merged.extend_from_slice(&r[j..]);
+    merged
+}
+
+#[derive(Debug, Clone)]
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn new(bits: usize) -> Self {
+        Self {
+            words: vec![0; bits.div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, bit: usize) {
+        self.words[bit / 64] |= 1 << (bit % 64);
+    }
+
+    fn union_with(&mut self, other: &BitSet) {
+        for (l, r) in self.words.iter_mut().zip(other.words.iter()) {
+            *l |= r;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peak_sets_switch_to_a_bitset_once_smaller() {
+        let peaks = 320;
+        let mut set = PeakSet::Sparse(vec![1, 5]);
+        set.union_with(&PeakSet::Sparse(vec![2, 5, 7]), peaks);
+        assert!(matches!(&set, PeakSet::Sparse(l) if l == &[1, 2, 5, 7]));
+
+        set.union_with(&PeakSet::Sparse(vec![0, 3]), peaks);
+        assert!(matches!(set, PeakSet::Dense(_)));
+        set.union_with(&PeakSet::Sparse(vec![3, 100]), peaks);
+        assert_eq!(set.len(), 7);
+
+        let mut sparse = PeakSet::Sparse(vec![127]);
+        sparse.union_with(&set, peaks);
+        assert_eq!(sparse.len(), 8);
+    }
 }
@@ -1,5 +1,6 @@
 use std::{collections::HashMap, ops::Rem};
 
+use adventofcode::cancel;
 use anyhow::Result;
 use tracing::info;
 
@@ -28,22 +29,23 @@ impl Args {
 }
 
 fn part1(input: String) -> Result<()> {
-    let stones = compute_arrangement(&input, 25);
+    let stones = compute_arrangement(&input, 25)?;
 
     println!("{stones}");
     Ok(())
 }
 
 fn part2(input: String) -> Result<()> {
-    let stones = compute_arrangement_precompute(&input, 75, 1..=10, 40);
+    let stones = compute_arrangement_precompute(&input, 75, 1..=10, 40)?;
 
     println!("{stones}");
     Ok(())
 }
 
-fn compute_arrangement(input: &str, blinks: usize) -> usize {
+fn compute_arrangement(input: &str, blinks: usize) -> Result<usize> {
     let mut stones: Vec<u128> = input.split_whitespace().map(|s| s.parse().unwrap()).collect();
     for i in 0..blinks {
+        cancel::check()?;
         info!("blink:{i}");
         let mut new_arrangement = Vec::new();
         for stone in stones.iter() {
@@ -52,7 +54,7 @@ fn compute_arrangement(input: &str, blinks: usize) -> usize {
         stones = new_arrangement;
         info!("blink:{i} (stones:{})", stones.len());
     }
-    stones.len()
+    Ok(stones.len())
 }
 
 fn compute_arrangement_precompute(
@@ -60,7 +62,7 @@ fn compute_arrangement_precompute(
     blinks: usize,
     pc_range: impl IntoIterator<Item = u128>,
     pc_blinks: usize,
-) -> usize {
+) -> Result<usize> {
     let mut stones: Vec<u128> = input.split_whitespace().map(|s| s.parse().unwrap()).collect();
 
     let precomputed: HashMap<u128, Vec<u128>> = pc_range
@@ -69,6 +71,7 @@ fn compute_arrangement_precompute(
             let mut values = Vec::new();
             let mut stones = vec![val];
             for i in 0..pc_blinks {
+                cancel::check()?;
                 info!("precomputed {val} blink:{i}");
                 let mut new_arrangement = Vec::new();
                 for stone in stones.iter() {
@@ -78,13 +81,14 @@ fn compute_arrangement_precompute(
                 stones = new_arrangement;
                 info!("blink:{i} (stones:{})", stones.len());
             }
-            (val, values)
+            Ok((val, values))
         })
-        .collect();
+        .collect::<Result<_>>()?;
     info!("precomputed: {precomputed:?}");
 
     let mut precomputed_count = 0;
     for i in 0..blinks {
+        cancel::check()?;
         info!("blink:{i}");
         let mut new_arrangement = Vec::new();
         for stone in stones.iter() {
@@ -99,7 +103,7 @@ fn compute_arrangement_precompute(
         stones = new_arrangement;
         info!("blink:{i} (stones:{}) (precomputed:{precomputed_count})", stones.len());
     }
-    stones.len() + precomputed_count as usize
+    Ok(stones.len() + precomputed_count as usize)
 }
 
 fn core(stone: u128) -> Vec<u128> {
@@ -129,3 +133,24 @@ fn core(stone: u128) -> Vec<u128> {
     // the old stone's number multiplied by 2024 is engraved on the new stone.
     vec![stone * 2024]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn core_applies_the_first_matching_rule() {
+        assert_eq!(core(0), vec![1]);
+        assert_eq!(core(1), vec![2024]);
+        assert_eq!(core(10), vec![1, 0]);
+        assert_eq!(core(99), vec![9, 9]);
+        assert_eq!(core(1000), vec![10, 0]);
+        assert_eq!(core(125), vec![253000]);
+    }
+
+    #[test]
+    fn compute_arrangement_example() {
+        assert_eq!(compute_arrangement("125 17", 6).unwrap(), 22);
+        assert_eq!(compute_arrangement("125 17", 25).unwrap(), 55312);
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use adventofcode::region::Region;
+use tracing::warn;
+
+// Pixels per garden plot.
+const CELL: usize = 24;
+// Fences are drawn this far inside their region, so that the two fences between neighbouring
+// regions don't overlap.
+const INSET: usize = 3;
+// Consecutive sides of a region cycle through these strokes.
+const STROKES: [(&str, &str); 4] = [
+    ("#1b1b1b", "none"),
+    ("#c0392b", "none"),
+    ("#1f4e9c", "6 3"),
+    ("#2e7d32", "2 2"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Facing {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+// A straight run of fence, from `start` to `end` (inclusive) along the row or column `line`.
+#[derive(Debug)]
+struct Side {
+    facing: Facing,
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Draw every region in its own colour, with its fence sides outlined and a label giving its
+/// area, perimeter and sides.
+///
+/// Sides are rebuilt here from the fence segments of each plot rather than taken from the
+/// region, so any disagreement with [`Region::sides`] shows up as a red label and a warning.
+pub(super) fn svg(regions: &[Region]) -> String {
+    let width = regions.iter().map(|r| r.bounding_box.1 .0 + 1).max().unwrap_or(0);
+    let height = regions.iter().map(|r| r.bounding_box.1 .1 + 1).max().unwrap_or(0);
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {0} {1}">"#,
+        width * CELL,
+        height * CELL
+    )
+    .unwrap();
+    writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+    for (i, region) in regions.iter().enumerate() {
+        draw_region(&mut out, i, region);
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn draw_region(out: &mut String, index: usize, region: &Region) {
+    let sides = sides(region);
+    if sides.len() != region.sides {
+        warn!(
+            "region {}{index}: {} sides counted, {} fence sides drawn",
+            region.label,
+            region.sides,
+            sides.len()
+        );
+    }
+    let summary = format!(
+        "{} area:{} perimeter:{} sides:{}",
+        region.label,
+        region.area(),
+        region.perimeter,
+        region.sides
+    );
+
+    writeln!(out, r#"<g id="region-{index}">"#).unwrap();
+    writeln!(out, "<title>{}</title>", escape(&summary)).unwrap();
+    // spread the hues around the colour wheel so that neighbouring regions rarely look alike
+    let hue = (index * 137) % 360;
+    for (x, y) in region.cells.iter() {
+        writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" fill="hsl({hue}, 65%, 80%)"/>"#,
+            x * CELL,
+            y * CELL
+        )
+        .unwrap();
+    }
+    for (i, side) in sides.iter().enumerate() {
+        let ((x1, y1), (x2, y2)) = side.endpoints();
+        let (stroke, dashes) = STROKES[i % STROKES.len()];
+        writeln!(
+            out,
+            r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{stroke}" stroke-width="2" stroke-dasharray="{dashes}" stroke-linecap="round"/>"#
+        )
+        .unwrap();
+    }
+
+    // the first plot found is the top-left one, which always has room for the start of the label
+    let (x, y) = region.cells[0];
+    let colour = if sides.len() == region.sides { "black" } else { "red" };
+    writeln!(
+        out,
+        r#"<text x="{}" y="{}" font-family="monospace" font-size="{}" fill="{colour}">{} a{} p{} s{}</text>"#,
+        x * CELL + INSET + 1,
+        y * CELL + CELL / 2 + 3,
+        CELL / 3,
+        escape(&region.label.to_string()),
+        region.area(),
+        region.perimeter,
+        region.sides
+    )
+    .unwrap();
+    writeln!(out, "</g>").unwrap();
+}
+
+// Every plot edge facing out of the region is a fence segment. Segments facing the same way on
+// the same line merge into a single side for as long as they are contiguous.
+fn sides(region: &Region) -> Vec<Side> {
+    let cells: HashSet<(usize, usize)> = region.cells.iter().copied().collect();
+    let contains = |x: isize, y: isize| x >= 0 && y >= 0 && cells.contains(&(x as usize, y as usize));
+
+    let mut segments: BTreeMap<(Facing, usize), Vec<usize>> = BTreeMap::new();
+    for (x, y) in region.cells.iter().copied() {
+        let (ix, iy) = (x as isize, y as isize);
+        if !contains(ix, iy - 1) {
+            segments.entry((Facing::Up, y)).or_default().push(x);
+        }
+        if !contains(ix, iy + 1) {
+            segments.entry((Facing::Down, y)).or_default().push(x);
+        }
+        if !contains(ix - 1, iy) {
+            segments.entry((Facing::Left, x)).or_default().push(y);
+        }
+        if !contains(ix + 1, iy) {
+            segments.entry((Facing::Right, x)).or_default().push(y);
+        }
+    }
+
+    let mut sides = Vec::new();
+    for ((facing, line), mut positions) in segments {
+        positions.sort();
+        let mut start = positions[0];
+        for (prev, pos) in positions.iter().zip(positions.iter().skip(1)) {
+            if *pos != prev + 1 {
+                sides.push(Side {
+                    facing,
+                    line,
+                    start,
+                    end: *prev,
+                });
+                start = *pos;
+            }
+        }
+        sides.push(Side {
+            facing,
+            line,
+            start,
+            end: *positions.last().unwrap(),
+        });
+    }
+    sides
+}
+
+// Labels are whatever characters the map uses, so they may need escaping as XML text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Side {
+    fn endpoints(&self) -> ((usize, usize), (usize, usize)) {
+        let (from, to) = (self.start * CELL + INSET, (self.end + 1) * CELL - INSET);
+        match self.facing {
+            Facing::Up => ((from, self.line * CELL + INSET), (to, self.line * CELL + INSET)),
+            Facing::Down => (
+                (from, (self.line + 1) * CELL - INSET),
+                (to, (self.line + 1) * CELL - INSET),
+            ),
+            Facing::Left => ((self.line * CELL + INSET, from), (self.line * CELL + INSET, to)),
+            Facing::Right => (
+                ((self.line + 1) * CELL - INSET, from),
+                ((self.line + 1) * CELL - INSET, to),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use adventofcode::region;
+
+    use super::*;
+
+    #[test]
+    fn sides_match_region_count_when_touching_diagonally() {
+        let grid: Vec<Vec<char>> = ["AAAAAA", "AAABBA", "AAABBA", "ABBAAA", "ABBAAA", "AAAAAA"]
+            .iter()
+            .map(|l| l.chars().collect())
+            .collect();
+        for region in region::regions(&grid) {
+            assert_eq!(sides(&region).len(), region.sides, "region {}", region.label);
+        }
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        let grid: Vec<Vec<char>> = ["<&", "\"'"].iter().map(|l| l.chars().collect()).collect();
+        let svg = svg(&region::regions(&grid));
+        assert!(svg.contains("<title>&lt; area:1"));
+        assert!(svg.contains(">&amp; a1 p4 s4</text>"));
+        assert!(svg.contains("<title>&quot; area:1"));
+        assert!(svg.contains(">&apos; a1 p4 s4</text>"));
+    }
+}
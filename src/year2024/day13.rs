@@ -1,7 +1,9 @@
 use std::ops::Rem;
 
-use anyhow::Result;
-use itertools::Itertools;
+use adventofcode::input::Input;
+use adventofcode::math::extended_gcd;
+use adventofcode::parallel;
+use anyhow::{anyhow, Result};
 use regex::Regex;
 use tracing::info;
 
@@ -9,6 +11,14 @@ use tracing::info;
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Token cost of pressing the A button.
+    #[arg(long, default_value_t = 3, global = true)]
+    cost_a: u32,
+
+    /// Token cost of pressing the B button.
+    #[arg(long, default_value_t = 1, global = true)]
+    cost_b: u32,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -17,69 +27,70 @@ pub enum Commands {
     Part1,
 
     /// Part 2.
-    Part2,
+    Part2 {
+        /// Unit conversion error added to both prize coordinates.
+        #[arg(long, default_value_t = 10_000_000_000_000)]
+        offset: i64,
+    },
 }
 
 impl Args {
-    pub fn run(self, input: String) -> Result<()> {
+    pub fn run(self, input: Input) -> Result<()> {
+        let costs = (self.cost_a.into(), self.cost_b.into());
         match self.command {
-            Commands::Part1 => part1(input),
-            Commands::Part2 => part2(input),
+            Commands::Part1 => part1(input, costs),
+            Commands::Part2 { offset } => part2(input, costs, offset),
         }
     }
 }
 
-fn part1(input: String) -> Result<()> {
-    let problems = parse_problems(&input);
-    info!("problems: {problems:?}");
-
-    let solved: Vec<_> = problems.iter().map(|p| p.solve()).collect();
-    info!("solved: {solved:?}");
-
-    let costs = (3, 1);
-    let tokens: i64 = solved
-        .into_iter()
-        .flatten()
-        .map(|(a, b)| (a * costs.0) + (b * costs.1))
-        .sum();
+fn part1(input: Input, costs: (i128, i128)) -> Result<()> {
+    let mut tokens: i128 = 0;
+    let solutions = parallel::map(parse_problems(&input)?, |problem| {
+        problem.map(|problem| {
+            let solved = problem.solve(costs);
+            (problem, solved)
+        })
+    });
+    for solution in solutions {
+        let (problem, solved) = solution?;
+        info!("problem: {problem:?} solved: {solved:?}");
+        if let Some((a, b)) = solved {
+            tokens += (a * costs.0) + (b * costs.1);
+        }
+    }
 
     println!("{tokens}");
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
-    let mut problems = parse_problems(&input);
-    info!("problems: {problems:?}");
-
-    let unit_conversion_error = 10000000000000;
-    let solved: Vec<_> = problems
-        .iter_mut()
-        .update(|p| {
-            p.prize.0 += unit_conversion_error;
-            p.prize.1 += unit_conversion_error;
-        })
-        .map(|p| p.solve())
-        .collect();
-    info!("solved: {solved:?}");
-
-    let costs = (3, 1);
-    let tokens: i64 = solved
-        .into_iter()
-        .flatten()
-        .map(|(a, b)| (a * costs.0) + (b * costs.1))
-        .sum();
+fn part2(input: Input, costs: (i128, i128), unit_conversion_error: i64) -> Result<()> {
+    let mut tokens: i128 = 0;
+    let solutions = parallel::map(parse_problems(&input)?, |problem| {
+        let problem = problem?.offset_prize(unit_conversion_error)?;
+        let solved = problem.solve(costs);
+        anyhow::Ok((problem, solved))
+    });
+    for solution in solutions {
+        let (problem, solved) = solution?;
+        info!("problem: {problem:?} solved: {solved:?}");
+        if let Some((a, b)) = solved {
+            tokens += (a * costs.0) + (b * costs.1);
+        }
+    }
 
     println!("{tokens}");
     Ok(())
 }
 
-fn parse_problems(input: &str) -> Vec<Problem> {
+// Problems are yielded as soon as their prize line is read, so only one is held in memory (unless
+// the `parallel` feature collects them all).
+fn parse_problems(input: &Input) -> Result<impl Iterator<Item = Result<Problem>>> {
     let button_re = Regex::new(r"Button ([AB]): X\+(\d+), Y\+(\d+)").unwrap();
     let prize_re = Regex::new(r"Prize: X=(\d+), Y=(\d+)").unwrap();
 
-    let mut problems = Vec::new();
     let mut current_problem = Problem::default();
-    for line in input.lines() {
+    let problems = input.parse_lines(move |line| {
         if let Some(cap) = button_re.captures(line) {
             let (_, [ab, x, y]) = cap.extract();
             let button = (x.parse().unwrap(), y.parse().unwrap());
@@ -88,16 +99,16 @@ fn parse_problems(input: &str) -> Vec<Problem> {
                 "B" => current_problem.button_b = button,
                 v => panic!("invalid Button: {v}"),
             }
-            continue;
+            return Ok(None);
         };
         if let Some(cap) = prize_re.captures(line) {
             let (_, [x, y]) = cap.extract();
             current_problem.prize = (x.parse().unwrap(), y.parse().unwrap());
-            problems.push(current_problem);
-            current_problem = Problem::default();
+            return Ok(Some(std::mem::take(&mut current_problem)));
         };
-    }
-    problems
+        Ok(None)
+    })?;
+    Ok(problems.filter_map(Result::transpose))
 }
 
 #[derive(Debug, Clone, Default)]
@@ -108,6 +119,15 @@ struct Problem {
 }
 
 impl Problem {
+    fn offset_prize(mut self, offset: i64) -> Result<Self> {
+        let shift = |v: i64| {
+            v.checked_add(offset)
+                .ok_or_else(|| anyhow!("prize coordinate {v} overflows with offset {offset}"))
+        };
+        self.prize = (shift(self.prize.0)?, shift(self.prize.1)?);
+        Ok(self)
+    }
+
     // Solve two linear equations:
     //   X1a + X2b = pX
     //   Y1a + Y2b = pY
@@ -147,48 +167,206 @@ impl Problem {
     // a1c2(109650) - c1a2(676820)  = -567170
     // ---------------------------- = ------- = 86 = b
     // a1b2(629)    - b1a2(7224)    = -6,595
-    fn solve(&self) -> Option<(i64, i64)> {
-        cramers_rule(
-            (self.button_a.0, self.button_b.0, self.prize.0),
-            (self.button_a.1, self.button_b.1, self.prize.1),
-        )
+    //
+    // When the determinant is zero, both buttons move the claw along the same line and Cramer's
+    // rule doesn't apply; see `collinear` for that case.
+    fn solve(&self, costs: (i128, i128)) -> Option<(i128, i128)> {
+        let eq1 = (self.button_a.0.into(), self.button_b.0.into(), self.prize.0.into());
+        let eq2 = (self.button_a.1.into(), self.button_b.1.into(), self.prize.1.into());
+        match cramers_rule(eq1, eq2) {
+            Solution::Unique(x, y) if x >= 0 && y >= 0 => Some((x, y)),
+            Solution::Unique(_, _) | Solution::None => None,
+            Solution::Degenerate => collinear(eq1, eq2, costs),
+        }
     }
 }
 
+#[derive(Debug, PartialEq)]
+enum Solution {
+    Unique(i128, i128),
+    // The determinant is zero: either no solution or infinitely many.
+    Degenerate,
+    None,
+}
+
 // In linear algebra, Cramer's rule is an explicit formula for the solution of a system of linear
 // equations with as many equations as unknowns, valid whenever the system has a unique solution.
 // https://en.wikipedia.org/wiki/Cramer%27s_rule
-fn cramers_rule(eq1: (i64, i64, i64), eq2: (i64, i64, i64)) -> Option<(i64, i64)> {
+//
+// Only integer solutions are accepted. Overflowing the intermediate products is treated as having
+// no solution.
+fn cramers_rule(eq1: (i128, i128, i128), eq2: (i128, i128, i128)) -> Solution {
     let (a1, b1, c1) = eq1;
     let (a2, b2, c2) = eq2;
 
-    let denom = (a1 * b2) - (b1 * a2);
+    let Some(denom) = det(a1, b1, a2, b2) else {
+        return Solution::None;
+    };
     info!("[{eq1:?} {eq2:?}] denom: {denom}");
     if denom == 0 {
-        return None;
+        return Solution::Degenerate;
     }
 
-    let x_nom = (c1 * b2) - (b1 * c2);
-    let y_nom = (a1 * c2) - (c1 * a2);
+    let (Some(x_nom), Some(y_nom)) = (det(c1, b1, c2, b2), det(a1, c1, a2, c2)) else {
+        return Solution::None;
+    };
     info!("[{eq1:?} {eq2:?}] x_nom: {x_nom}");
     info!("[{eq1:?} {eq2:?}] y_nom: {y_nom}");
-    if x_nom == 0 || y_nom == 0 {
-        return None;
-    }
 
     let x_nom_rem = x_nom.rem(denom);
     info!("[{eq1:?} {eq2:?}] x_nom_rem: {x_nom_rem}");
     if x_nom_rem != 0 {
-        return None;
+        return Solution::None;
     }
     let y_nom_rem = y_nom.rem(denom);
     info!("[{eq1:?} {eq2:?}] y_nom_rem: {y_nom_rem}");
     if y_nom_rem != 0 {
+        return Solution::None;
+    }
+
+    Solution::Unique(x_nom / denom, y_nom / denom)
+}
+
+// |a1 b1|
+// |a2 b2| = a1b2 - b1a2
+fn det(a1: i128, b1: i128, a2: i128, b2: i128) -> Option<i128> {
+    a1.checked_mul(b2)?.checked_sub(b1.checked_mul(a2)?)
+}
+
+// Both equations are multiples of each other (or inconsistent). Reduce the system to a single
+// linear Diophantine equation `ax + by = c` and find its cheapest non-negative solution.
+fn collinear(eq1: (i128, i128, i128), eq2: (i128, i128, i128), costs: (i128, i128)) -> Option<(i128, i128)> {
+    let (a1, b1, c1) = eq1;
+    let (a2, b2, c2) = eq2;
+
+    // The augmented matrix must also be of rank <= 1 for the system to be consistent.
+    if det(a1, c1, a2, c2)? != 0 || det(b1, c1, b2, c2)? != 0 {
         return None;
     }
+    let (a, b, c) = if (a1, b1) != (0, 0) { eq1 } else { eq2 };
+    if (a, b) == (0, 0) {
+        return (c1 == 0 && c2 == 0).then_some((0, 0));
+    }
+    // Only one button moves the claw, never press the other.
+    if a == 0 {
+        return (c.rem(b) == 0 && c / b >= 0).then(|| (0, c / b));
+    }
+    if b == 0 {
+        return (c.rem(a) == 0 && c / a >= 0).then(|| (c / a, 0));
+    }
+    cheapest_diophantine((a, b, c), costs)
+}
+
+// All integer solutions of `ax + by = c` are, with `g = gcd(a, b)` and `(x0, y0)` a particular
+// solution from the extended Euclidean algorithm:
+//
+//   x = x0 + k(b/g)
+//   y = y0 - k(a/g)
+//
+// Requiring `x >= 0` and `y >= 0` bounds `k`, and since the cost is linear in `k`, the cheapest
+// solution lies on one of those bounds.
+fn cheapest_diophantine((a, b, c): (i128, i128, i128), costs: (i128, i128)) -> Option<(i128, i128)> {
+    let (g, x, y) = extended_gcd(a, b);
+    if c.rem(g) != 0 {
+        return None;
+    }
+    let scale = c / g;
+    let (x0, y0) = (x.checked_mul(scale)?, y.checked_mul(scale)?);
+    let (step_x, step_y) = (b / g, -(a / g));
+    info!("[{a}x + {b}y = {c}] x0:{x0} y0:{y0} step:({step_x}, {step_y})");
+
+    let (mut lo, mut hi): (Option<i128>, Option<i128>) = (None, None);
+    for (v0, step) in [(x0, step_x), (y0, step_y)] {
+        // v0 + k * step >= 0
+        if step > 0 {
+            let bound = div_ceil(-v0, step);
+            lo = Some(lo.map_or(bound, |lo| lo.max(bound)));
+        } else {
+            let bound = div_floor(v0, -step);
+            hi = Some(hi.map_or(bound, |hi| hi.min(bound)));
+        }
+    }
+    if let (Some(lo), Some(hi)) = (lo, hi) {
+        if lo > hi {
+            return None;
+        }
+    }
+
+    let slope = costs.0.checked_mul(step_x)?.checked_add(costs.1.checked_mul(step_y)?)?;
+    let k = match slope {
+        s if s > 0 => lo?,
+        s if s < 0 => hi?,
+        _ => lo.or(hi)?,
+    };
+    Some((
+        x0.checked_add(k.checked_mul(step_x)?)?,
+        y0.checked_add(k.checked_mul(step_y)?)?,
+    ))
+}
+
+fn div_floor(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) {
+        q - 1
+    } else {
+        q
+    }
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) == (b < 0)) {
+        q + 1
+    } else {
+        q
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cramers_rule_unique_solution() {
+        assert_eq!(cramers_rule((94, 22, 8400), (34, 67, 5400)), Solution::Unique(80, 40));
+        assert_eq!(cramers_rule((17, 84, 7870), (86, 37, 6450)), Solution::Unique(38, 86));
+    }
+
+    #[test]
+    fn cramers_rule_no_integer_solution() {
+        assert_eq!(cramers_rule((26, 67, 12748), (66, 21, 12176)), Solution::None);
+    }
+
+    #[test]
+    fn cramers_rule_degenerate() {
+        assert_eq!(cramers_rule((2, 4, 10), (2, 4, 10)), Solution::Degenerate);
+        assert_eq!(cramers_rule((1, 2, 3), (2, 4, 7)), Solution::Degenerate);
+    }
 
-    let x = x_nom / denom;
-    let y = y_nom / denom;
+    #[test]
+    fn solve_collinear_buttons_picks_the_cheapest() {
+        let problem = Problem {
+            button_a: (2, 2),
+            button_b: (4, 4),
+            prize: (10, 10),
+        };
+        assert_eq!(problem.solve((3, 1)), Some((1, 2)));
+        assert_eq!(problem.solve((1, 3)), Some((5, 0)));
 
-    Some((x, y))
+        let unreachable = Problem {
+            prize: (10, 11),
+            ..problem
+        };
+        assert_eq!(unreachable.solve((3, 1)), None);
+    }
+    #[test]
+    fn offset_overflowing_the_prize_is_an_error() {
+        let problem = Problem {
+            button_a: (94, 34),
+            button_b: (22, 67),
+            prize: (8400, 5400),
+        };
+        assert_eq!(problem.clone().offset_prize(10).unwrap().prize, (8410, 5410));
+        assert!(problem.offset_prize(i64::MAX).is_err());
+    }
 }
@@ -1,17 +1,39 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::ops::{Deref, Rem};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::LazyLock;
 
-use anyhow::Result;
+use adventofcode::animation::AnimationArgs;
+use adventofcode::cancel;
+use adventofcode::input::Input;
+use adventofcode::math::{extended_gcd, lcm};
+use adventofcode::render::{self, Image};
+use anyhow::{anyhow, bail, Context, Result};
 use regex::Regex;
-use tracing::info;
+use tracing::{info, info_span};
 
 #[derive(Debug, clap::Args)]
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Width of the space the robots move in. Inferred from the robot positions when omitted.
+    #[arg(long, global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    width: Option<u32>,
+
+    /// Height of the space the robots move in. Inferred from the robot positions when omitted.
+    #[arg(long, global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    height: Option<u32>,
+
+    /// Save an image of the robots' final positions to this file (.png or .ppm).
+    #[arg(long, global = true)]
+    render: Option<PathBuf>,
+
+    // part 2 records the steps scanned by the detector, then jumps to the picture
+    #[command(flatten)]
+    animation: AnimationArgs,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -20,24 +42,46 @@ pub enum Commands {
     Part1,
 
     /// Part 2.
-    Part2,
+    Part2 {
+        /// Write the detector's per-step variance scores to this CSV file.
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
 }
 
 impl Args {
-    pub fn run(self, input: String) -> Result<()> {
+    pub fn run(self, input: Input) -> Result<()> {
+        let dimensions = (self.width, self.height);
         match self.command {
-            Commands::Part1 => part1(input),
-            Commands::Part2 => part2(input),
+            Commands::Part1 => part1(input, dimensions, self.render.as_deref(), &self.animation),
+            Commands::Part2 { csv } => part2(input, dimensions, csv, self.render.as_deref(), &self.animation),
         }
     }
 }
 
-fn part1(input: String) -> Result<()> {
-    let mut robots = Robots::from_str(&input).unwrap();
+fn part1(
+    input: Input,
+    dimensions: (Option<u32>, Option<u32>),
+    render: Option<&Path>,
+    animation: &AnimationArgs,
+) -> Result<()> {
+    let mut robots =
+        info_span!("parse").in_scope(|| Robots::from_lines(input.lines()?)?.with_dimensions(dimensions))?;
     info!("robots: {robots:?}");
 
     let iterations = 100;
-    (0..iterations).for_each(|_| robots.move_once());
+    let mut recorder = animation.recorder()?;
+    if recorder.is_enabled() {
+        // jumping straight to the end would leave nothing to animate
+        recorder.record(|| robots.image());
+        for _ in 0..iterations {
+            cancel::check()?;
+            robots.move_once();
+            recorder.record(|| robots.image());
+        }
+    } else {
+        robots.move_to(iterations);
+    }
     info!("new_robots (i={iterations}): {robots:?}");
 
     let quadrants = robots.compute_quadrants();
@@ -45,68 +89,170 @@ fn part1(input: String) -> Result<()> {
     info!("q1:{q1}, q2:{q2} q3:{q3}, q4:{q4}");
 
     let safety_factor = q1 * q2 * q3 * q4;
+    recorder.finish(|| robots.image())?;
+
     println!("{safety_factor}");
+
+    if let Some(path) = render {
+        robots.image().save(path)?;
+    }
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
-    let mut robots = Robots::from_str(&input).unwrap();
+fn part2(
+    input: Input,
+    dimensions: (Option<u32>, Option<u32>),
+    csv: Option<PathBuf>,
+    render: Option<&Path>,
+    animation: &AnimationArgs,
+) -> Result<()> {
+    let mut robots =
+        info_span!("parse").in_scope(|| Robots::from_lines(input.lines()?)?.with_dimensions(dimensions))?;
     info!("robots: {robots:?}");
 
-    let max_iterations = 10_000;
-    let mut list: Vec<_> = (0..max_iterations)
-        .map(|i| {
-            if i != 0 {
-                robots.move_once();
-            }
-            // I got this idea from reddit.. still very satisfying!
-            //
-            // ###############################
-            // #.............................#
-            // #.............................#
-            // #.............................#
-            // #.............................#
-            // #..............#..............#
-            // #.............###.............#
-            // #............#####............#
-            // #...........#######...........#
-            // #..........#########..........#
-            // #............#####............#
-            // #...........#######...........#
-            // #..........#########..........#
-            // #.........###########.........#
-            // #........#############........#
-            // #..........#########..........#
-            // #.........###########.........#
-            // #........#############........#
-            // #.......###############.......#
-            // #......#################......#
-            // #........#############........#
-            // #.......###############.......#
-            // #......#################......#
-            // #.....###################.....#
-            // #....#####################....#
-            // #.............###.............#
-            // #.............###.............#
-            // #.............###.............#
-            // #.............................#
-            // #.............................#
-            // #.............................#
-            // #.............................#
-            // ###############################
-            let neighboor_prob_score = robots.compute_neighboor_prob_score();
-            (neighboor_prob_score, robots.clone(), i)
-        })
-        .collect();
-    list.sort_by(|a, b| b.0.cmp(&a.0));
+    let mut detector = PatternDetector::new(robots.width, robots.height);
+    let steps = robots.width.max(robots.height);
+    let mut recorder = animation.recorder()?;
+    for step in 0..steps {
+        cancel::check()?;
+        if step != 0 {
+            robots.move_once();
+        }
+        detector.observe(step, &robots);
+        recorder.record(|| robots.image());
+    }
+
+    if let Some(path) = csv {
+        std::fs::write(&path, detector.csv()).with_context(|| format!("writing csv file '{}'", path.display()))?;
+    }
 
-    let candidate = list.first().unwrap();
-    info!("iteration {}:\n{}", candidate.0, candidate.1);
+    let step = detector.candidate()?;
+    // the robots were left at step `steps - 1`, wrap around the period to get back to `step`
+    robots.move_to(robots.period() + step as u64 - (steps as u64 - 1));
+    info!("iteration {step}:\n{robots}");
 
-    println!("{}", candidate.2);
+    recorder.finish(|| robots.image())?;
+
+    println!("{step}");
+
+    if let Some(path) = render {
+        robots.image().save(path)?;
+    }
     Ok(())
 }
 
+// Robots move independently along each axis, so the x coordinates repeat every `width` steps and
+// the y coordinates every `height` steps. When the robots gather into a picture, both axes are
+// unusually concentrated at the same time:
+//
+// ###############################
+// #.............................#
+// #.............................#
+// #.............................#
+// #.............................#
+// #..............#..............#
+// #.............###.............#
+// #............#####............#
+// #...........#######...........#
+// #..........#########..........#
+// #............#####............#
+// #...........#######...........#
+// #..........#########..........#
+// #.........###########.........#
+// #........#############........#
+// #..........#########..........#
+// #.........###########.........#
+// #........#############........#
+// #.......###############.......#
+// #......#################......#
+// #........#############........#
+// #.......###############.......#
+// #......#################......#
+// #.....###################.....#
+// #....#####################....#
+// #.............###.............#
+// #.............###.............#
+// #.............###.............#
+// #.............................#
+// #.............................#
+// #.............................#
+// #.............................#
+// ###############################
+//
+// Instead of looking for the picture in every one of the lcm(width, height) states, find the
+// step with the lowest variance within each axis period separately, then combine both with the
+// Chinese Remainder Theorem:
+//   t = tx (mod width)
+//   t = ty (mod height)
+#[derive(Debug)]
+struct PatternDetector {
+    width: u32,
+    height: u32,
+    scores: Vec<(u32, Option<f64>, Option<f64>)>,
+    best_x: Option<(u32, f64)>,
+    best_y: Option<(u32, f64)>,
+}
+
+impl PatternDetector {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            scores: Vec::new(),
+            best_x: None,
+            best_y: None,
+        }
+    }
+
+    fn observe(&mut self, step: u32, robots: &Robots) {
+        let x_score = (step < self.width).then(|| variance(robots.iter().map(|r| r.position.0)));
+        let y_score = (step < self.height).then(|| variance(robots.iter().map(|r| r.position.1)));
+        for (score, best) in [(x_score, &mut self.best_x), (y_score, &mut self.best_y)] {
+            let Some(score) = score else {
+                continue;
+            };
+            if best.is_none_or(|(_, b)| score < b) {
+                *best = Some((step, score));
+            }
+        }
+        self.scores.push((step, x_score, y_score));
+    }
+
+    fn candidate(&self) -> Result<u32> {
+        let (Some((tx, _)), Some((ty, _))) = (self.best_x, self.best_y) else {
+            return Err(anyhow!("no step observed"));
+        };
+        info!("x period:{} best:{tx}, y period:{} best:{ty}", self.width, self.height);
+        crt((tx as u64, self.width as u64), (ty as u64, self.height as u64))
+            .map(|t| t as u32)
+            .ok_or(anyhow!(
+                "no step satisfies t={tx} (mod {}) and t={ty} (mod {})",
+                self.width,
+                self.height
+            ))
+    }
+
+    fn csv(&self) -> String {
+        let mut csv = String::from("step,x_variance,y_variance\n");
+        for (step, x, y) in self.scores.iter() {
+            let fmt = |v: &Option<f64>| v.map(|v| format!("{v:.3}")).unwrap_or_default();
+            csv += &format!("{step},{},{}\n", fmt(x), fmt(y));
+        }
+        csv
+    }
+}
+
+fn variance(values: impl Iterator<Item = u32>) -> f64 {
+    let (n, sum, sum_sq) = values.fold((0_u64, 0_u64, 0_u64), |(n, sum, sum_sq), v| {
+        (n + 1, sum + v as u64, sum_sq + (v as u64 * v as u64))
+    });
+    if n == 0 {
+        return 0.0;
+    }
+    let mean = sum as f64 / n as f64;
+    (sum_sq as f64 / n as f64) - (mean * mean)
+}
+
 #[derive(Debug, Clone)]
 struct Robot {
     position: (u32, u32),
@@ -135,6 +281,20 @@ impl Robot {
             ((self.position.1 as i32 + self.velocity.1).rem(height) + height).rem(height) as u32,
         )
     }
+
+    // Position after `steps` moves, without simulating each of them:
+    //   p(n) = (p(0) + n * v) mod size
+    fn simulate_moves(&self, steps: u64, width: u32, height: u32) -> (u32, u32) {
+        let axis = |p: u32, v: i32, size: u32| {
+            let size = size as i64;
+            let steps = (steps % size as u64) as i64;
+            (p as i64 + v as i64 * steps).rem_euclid(size) as u32
+        };
+        (
+            axis(self.position.0, self.velocity.0, width),
+            axis(self.position.1, self.velocity.1, height),
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -144,10 +304,12 @@ struct Robots {
     height: u32,
 }
 
-impl FromStr for Robots {
-    type Err = ();
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        let robots: Vec<_> = s.lines().flat_map(Robot::from_str).collect();
+impl Robots {
+    fn from_lines(lines: impl Iterator<Item = Result<String>>) -> Result<Self> {
+        let mut robots = Vec::new();
+        for line in lines {
+            robots.extend(Robot::from_str(&line?));
+        }
         let (width, height) = {
             let (width, height) = robots.iter().fold((0, 0), |mut acc, r| {
                 if r.position.0 > acc.0 {
@@ -162,6 +324,14 @@ impl FromStr for Robots {
         };
         Ok(Self { robots, width, height })
     }
+
+    fn image(&self) -> Image {
+        let mut image = Image::new(self.width as usize, self.height as usize, render::BACKGROUND);
+        for robot in self.robots.iter() {
+            image.set((robot.position.0 as usize, robot.position.1 as usize), render::GREEN);
+        }
+        image
+    }
 }
 
 impl Deref for Robots {
@@ -209,12 +379,48 @@ impl Quadrants {
 }
 
 impl Robots {
+    // Explicit dimensions take precedence over the ones inferred from the robot positions, which
+    // are wrong whenever no robot sits on the last row or column.
+    fn with_dimensions(mut self, (width, height): (Option<u32>, Option<u32>)) -> Result<Self> {
+        if width.is_none() || height.is_none() {
+            info!("inferred dimensions: {}x{}", self.width, self.height);
+        }
+        self.width = width.unwrap_or(self.width);
+        self.height = height.unwrap_or(self.height);
+        if let Some(r) = self
+            .robots
+            .iter()
+            .find(|r| r.position.0 >= self.width || r.position.1 >= self.height)
+        {
+            bail!(
+                "robot at {:?} lies outside the {}x{} space",
+                r.position,
+                self.width,
+                self.height
+            );
+        }
+        Ok(self)
+    }
+
+    // Every robot moves with a constant velocity on a torus, so the whole simulation repeats
+    // after lcm(width, height) steps.
+    fn period(&self) -> u64 {
+        lcm(self.width as u64, self.height as u64)
+    }
+
     fn move_once(&mut self) {
         self.robots.iter_mut().for_each(|r| {
             r.position = r.simulate_move(self.width, self.height);
         });
     }
 
+    fn move_to(&mut self, steps: u64) {
+        let steps = steps % self.period();
+        self.robots.iter_mut().for_each(|r| {
+            r.position = r.simulate_moves(steps, self.width, self.height);
+        });
+    }
+
     fn compute_quadrants(&self) -> Quadrants {
         let is_even = (self.width.rem(2) == 0, self.height.rem(2) == 0);
         let center = (self.width / 2, self.height / 2);
@@ -233,23 +439,49 @@ impl Robots {
                 acc
             })
     }
+}
 
-    fn compute_neighboor_prob_score(&self) -> u32 {
-        let positions: HashSet<(u32, u32)> = HashSet::from_iter(self.robots.iter().map(|r| r.position));
+// Smallest non-negative `t` such that `t = a (mod m)` and `t = b (mod n)`, for any moduli (not
+// necessarily coprime).
+fn crt((a, m): (u64, u64), (b, n): (u64, u64)) -> Option<u64> {
+    let (a, m, b, n) = (a as i128, m as i128, b as i128, n as i128);
+    let (g, p, _) = extended_gcd(m, n);
+    if (b - a) % g != 0 {
+        return None;
+    }
+    let lcm = m / g * n;
+    let t = a + m * ((b - a) / g * p).rem_euclid(n / g);
+    Some(t.rem_euclid(lcm) as u64)
+}
 
-        let directions = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
-        self.robots
-            .iter()
-            .flat_map(|r| {
-                directions.iter().map(|d| {
-                    let pos = (r.position.0 as i32 + d.0, r.position.1 as i32 + d.1);
-                    if pos.0 >= 0 && pos.1 >= 0 && positions.contains(&(pos.0 as u32, pos.1 as u32)) {
-                        1
-                    } else {
-                        0
-                    }
-                })
-            })
-            .sum()
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Debug, clap::Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: Args,
+    }
+
+    fn robots(lines: &[&str]) -> Robots {
+        Robots::from_lines(lines.iter().map(|l| Ok(l.to_string()))).unwrap()
+    }
+
+    #[test]
+    fn zero_dimensions_are_rejected() {
+        assert!(Cli::try_parse_from(["day14", "part1", "--width", "0"]).is_err());
+        assert!(Cli::try_parse_from(["day14", "part1", "--height", "0"]).is_err());
+        assert!(Cli::try_parse_from(["day14", "part1", "--width", "11", "--height", "7"]).is_ok());
+    }
+
+    #[test]
+    fn robots_outside_explicit_dimensions_are_rejected() {
+        let lines = ["p=0,4 v=3,-3", "p=10,3 v=-1,2"];
+        assert!(robots(&lines).with_dimensions((Some(11), Some(7))).is_ok());
+        assert!(robots(&lines).with_dimensions((Some(10), None)).is_err());
+        assert!(robots(&lines).with_dimensions((None, Some(4))).is_err());
     }
 }
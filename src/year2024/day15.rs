@@ -1,16 +1,43 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::fmt::Display;
-use std::marker::PhantomData;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
 use std::str::FromStr;
 
-use anyhow::Result;
+use adventofcode::animation::AnimationArgs;
+use adventofcode::cancel;
+use adventofcode::render::{self, Image, Palette};
+use anyhow::{anyhow, Context, Result};
 use itertools::Itertools;
 use tracing::{debug, info};
 
+mod play;
+
 #[derive(Debug, clap::Args)]
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Stop after this many moves and render the warehouse at that point.
+    #[arg(long, global = true)]
+    step: Option<usize>,
+
+    /// Write every frame (the warehouse after each move) to this text file.
+    #[arg(long, global = true)]
+    frames: Option<PathBuf>,
+
+    /// Save an image of the warehouse after the last move played to this file (.png or .ppm).
+    #[arg(long, global = true)]
+    render: Option<PathBuf>,
+
+    /// Scale the warehouse, as `N` (N cells wide) or `WxH`. Boxes get the same footprint.
+    /// Defaults to 1 for part 1 and 2 for part 2.
+    #[arg(long, global = true)]
+    scale: Option<BoxModel>,
+
+    #[command(flatten)]
+    animation: AnimationArgs,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -20,23 +47,46 @@ pub enum Commands {
 
     /// Part 2.
     Part2,
+
+    /// Move the robot around the warehouse interactively.
+    Play {
+        /// Start with the scaled warehouse (2 wide unless `--scale` is set).
+        #[arg(long)]
+        wide: bool,
+    },
 }
 
 impl Args {
     pub fn run(self, input: String) -> Result<()> {
+        let replay = Replay {
+            step: self.step,
+            frames: self.frames,
+            render: self.render,
+            animation: self.animation,
+        };
         match self.command {
-            Commands::Part1 => part1(input),
-            Commands::Part2 => part2(input),
+            Commands::Part1 => solve(input, replay, self.scale.unwrap_or(BoxModel::STANDARD)),
+            Commands::Part2 => solve(input, replay, self.scale.unwrap_or(BoxModel::WIDE)),
+            Commands::Play { .. } if replay.is_set() => Err(anyhow!(
+                "--step, --frames, --render, --gif and --animate don't apply to play"
+            )),
+            Commands::Play { wide } => play::play(
+                Map::from_str(&input).unwrap(),
+                self.scale.unwrap_or(BoxModel::WIDE),
+                wide,
+            ),
         }
     }
 }
 
-fn part1(input: String) -> Result<()> {
-    let mut map = Map::from_str(&input).unwrap();
-    info!("{map:?}");
+// Both parts play the same moves; part 2 scales the warehouse by `model` first, which widens the
+// boxes to 2x1 unless `--scale` says otherwise.
+fn solve(input: String, replay: Replay, model: BoxModel) -> Result<()> {
+    let mut map = Map::from_str(&input).unwrap().scale(model);
+    debug!("{map:?}");
     info!("\n{map}");
 
-    map.resolve();
+    replay.run(&mut map)?;
     info!("\n{map}");
 
     let coordinates = map.gps_coordinates();
@@ -45,37 +95,127 @@ fn part1(input: String) -> Result<()> {
     Ok(())
 }
 
-fn part2(input: String) -> Result<()> {
-    let mut map = Map::from_str(&input).unwrap().make_wide();
-    debug!("{map:?}");
-    info!("\n{map}");
+#[derive(Debug)]
+struct Replay {
+    step: Option<usize>,
+    frames: Option<PathBuf>,
+    render: Option<PathBuf>,
+    animation: AnimationArgs,
+}
 
-    map.resolve();
-    info!("\n{map}");
+impl Replay {
+    fn is_set(&self) -> bool {
+        self.step.is_some()
+            || self.frames.is_some()
+            || self.render.is_some()
+            || self.animation.gif.is_some()
+            || self.animation.animate
+    }
 
-    let coordinates = map.gps_coordinates();
-    let sum: usize = coordinates.iter().sum();
-    println!("{sum}");
-    Ok(())
+    // Play the moves one at a time, up to `step` if set. When stopping early, the warehouse is
+    // printed before the GPS sum so the two can be compared.
+    fn run(&self, map: &mut Map) -> Result<()> {
+        let mut frames = match &self.frames {
+            Some(path) => Some(BufWriter::new(
+                File::create(path).with_context(|| format!("creating frames file '{}'", path.display()))?,
+            )),
+            None => None,
+        };
+        let mut recorder = self.animation.recorder()?;
+        let total = map.moves.len();
+        if let Some(out) = frames.as_mut() {
+            writeln!(out, "move 0/{total}: start robot:{:?}\n{map}", map.robot)?;
+        }
+        recorder.record(|| map.image());
+
+        let mut played = 0;
+        let mut last = None;
+        while self.step.is_none_or(|step| played < step) {
+            cancel::check()?;
+            let Some(step) = map.step() else {
+                break;
+            };
+            played += 1;
+            debug!("move {played}/{total}: {step}");
+            if let Some(out) = frames.as_mut() {
+                writeln!(out, "move {played}/{total}: {step}\n{map}")?;
+            }
+            recorder.record(|| map.pushed_image(&step));
+            last = Some(step);
+        }
+        if let Some(out) = frames.as_mut() {
+            out.flush()?;
+        }
+        recorder.finish(|| map.image())?;
+
+        if self.step.is_some() {
+            match last {
+                Some(step) => println!("move {played}/{total}: {step}"),
+                None => println!("move 0/{total}: start robot:{:?}", map.robot),
+            }
+            print!("{map}");
+        }
+        if let Some(path) = &self.render {
+            map.image().save(path)?;
+        }
+        Ok(())
+    }
+}
+
+// Footprint of every box, in cells. The puzzle uses 1x1 boxes, then 2x1 boxes for part 2.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BoxModel {
+    width: usize,
+    height: usize,
+}
+
+impl BoxModel {
+    const STANDARD: Self = Self { width: 1, height: 1 };
+    const WIDE: Self = Self { width: 2, height: 1 };
+}
+
+impl FromStr for BoxModel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (width, height) = s.split_once('x').unwrap_or((s, "1"));
+        let model = Self {
+            width: width
+                .parse()
+                .with_context(|| format!("parsing scale width '{width}'"))?,
+            height: height
+                .parse()
+                .with_context(|| format!("parsing scale height '{height}'"))?,
+        };
+        if model.width == 0 || model.height == 0 {
+            return Err(anyhow!("scale must be at least 1x1"));
+        }
+        Ok(model)
+    }
 }
 
 #[derive(Debug, Clone)]
-struct Standard;
-#[derive(Debug, Clone)]
-struct Wide;
+struct Crate {
+    // top-left cell
+    pos: (usize, usize),
+}
 
 #[derive(Debug, Clone)]
-struct Map<State> {
-    map: Vec<Vec<char>>,
+struct Map {
+    model: BoxModel,
+    walls: Vec<Vec<bool>>,
+    boxes: Vec<Crate>,
+    // index into `boxes` of the box covering each cell
+    occupancy: Vec<Vec<Option<usize>>>,
     moves: VecDeque<char>,
-    _state: PhantomData<State>,
+    robot: (usize, usize),
 }
 
-impl FromStr for Map<Standard> {
+impl FromStr for Map {
     type Err = ();
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        let (map, moves) =
+        let (grid, moves) =
             s.lines()
                 .filter(|l| !l.trim().is_empty())
                 .fold((Vec::new(), VecDeque::new()), |mut acc, l| {
@@ -86,207 +226,253 @@ impl FromStr for Map<Standard> {
                     }
                     acc
                 });
-        Ok(Self {
-            map,
-            moves,
-            _state: PhantomData::<Standard>,
-        })
+
+        let mut walls = Vec::new();
+        let mut boxes = Vec::new();
+        let mut robot = None;
+        for (y, row) in grid.iter().enumerate() {
+            walls.push(row.iter().map(|c| *c == '#').collect());
+            for (x, c) in row.iter().enumerate() {
+                match c {
+                    'O' => boxes.push(Crate { pos: (x, y) }),
+                    '@' => robot = Some((x, y)),
+                    '#' | '.' => {}
+                    _ => unreachable!(),
+                }
+            }
+        }
+        Ok(Self::new(BoxModel::STANDARD, walls, boxes, moves, robot.ok_or(())?))
     }
 }
 
-impl<State> Display for Map<State> {
+impl Display for Map {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in self.map.iter() {
-            for c in row.iter() {
-                write!(f, "{c}").unwrap();
+        for (y, row) in self.walls.iter().enumerate() {
+            for (x, wall) in row.iter().enumerate() {
+                let c = match (self.occupancy[y][x], *wall) {
+                    _ if self.robot == (x, y) => '@',
+                    (_, true) => '#',
+                    (Some(id), _) => self.box_glyph(id, x),
+                    (None, _) => '.',
+                };
+                write!(f, "{c}")?;
             }
-            writeln!(f).unwrap();
+            writeln!(f)?;
         }
         Ok(())
     }
 }
 
-impl<State> Map<State> {
-    fn start_pos(&self) -> (usize, usize) {
-        self.map
-            .iter()
-            .enumerate()
-            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, c)| (x, y, c)))
-            .find(|(_, _, c)| *c == &'@')
-            .map(|(x, y, _)| (x, y))
-            .unwrap()
-    }
-
-    fn get_map_entry(&self, (x, y): (isize, isize)) -> char {
-        self.map[y as usize][x as usize]
-    }
+// What changed in the warehouse after a single move.
+#[derive(Debug, Clone)]
+struct Step {
+    mov: char,
+    robot: ((usize, usize), (usize, usize)),
+    // (from, to) of the top-left cell of every box pushed.
+    boxes: Vec<((usize, usize), (usize, usize))>,
+}
 
-    fn swap(&mut self, (x, y): (isize, isize), (new_x, new_y): (isize, isize)) {
-        let src_val = std::mem::replace(&mut self.map[y as usize][x as usize], ' ');
-        self.map[y as usize][x as usize] = std::mem::replace(&mut self.map[new_y as usize][new_x as usize], src_val);
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (from, to) = self.robot;
+        if from == to {
+            return write!(f, "{} robot:{from:?} blocked", self.mov);
+        }
+        write!(f, "{} robot:{from:?}->{to:?}", self.mov)?;
+        if !self.boxes.is_empty() {
+            let boxes = self
+                .boxes
+                .iter()
+                .map(|(from, to)| format!("{from:?}->{to:?}"))
+                .join(" ");
+            write!(f, " boxes:[{boxes}]")?;
+        }
+        Ok(())
     }
 }
 
-impl Map<Standard> {
-    fn resolve(&mut self) {
-        let mut pos = self.start_pos();
-        while let Some(mov) = self.moves.pop_front() {
-            let (mut x, mut y) = (pos.0 as isize, pos.1 as isize);
-
-            let mut can_swap = false;
-            let mut stack = Vec::new();
-            loop {
-                let entry = self.get_map_entry((x, y));
-                let (new_x, new_y) = new_pos((x, y), mov);
-                let new_entry = self.get_map_entry((new_x, new_y));
-                info!("pos:({x}, {y}) ({entry}) new_pos:({new_x}, {new_y}) ({new_entry}) mov:{mov}");
-                if new_entry == '#' {
-                    break;
-                }
-                stack.push(((x, y), (new_x, new_y)));
-                (x, y) = (new_x, new_y);
-                match new_entry {
-                    'O' => {
-                        continue;
-                    }
-                    '.' => {
-                        can_swap = true;
-                        break;
-                    }
-                    _ => unreachable!(),
-                }
-            }
-            if can_swap {
-                while let Some((left, right)) = stack.pop() {
-                    self.swap(left, right);
-                }
-                let new_pos = new_pos((pos.0 as isize, pos.1 as isize), mov);
-                pos = (new_pos.0 as usize, new_pos.1 as usize);
-            }
+impl Map {
+    fn new(
+        model: BoxModel,
+        walls: Vec<Vec<bool>>,
+        boxes: Vec<Crate>,
+        moves: VecDeque<char>,
+        robot: (usize, usize),
+    ) -> Self {
+        let occupancy = walls.iter().map(|row| vec![None; row.len()]).collect();
+        let mut map = Self {
+            model,
+            walls,
+            boxes,
+            occupancy,
+            moves,
+            robot,
+        };
+        for id in 0..map.boxes.len() {
+            map.occupy(id, Some(id));
         }
+        map
     }
 
-    fn gps_coordinates(&self) -> Vec<usize> {
-        self.map
+    // Every cell (wall, box, free space) becomes a `width` x `height` block. The robot keeps its
+    // single cell, in the top-left corner of its block.
+    fn scale(self, model: BoxModel) -> Self {
+        let walls = self
+            .walls
             .iter()
-            .enumerate()
-            .flat_map(|(y, row)| {
-                row.iter()
-                    .enumerate()
-                    .filter(|(_, c)| *c == &'O')
-                    .map(move |(x, _)| (y * 100) + x)
+            .flat_map(|row| {
+                let row: Vec<_> = row.iter().flat_map(|w| itertools::repeat_n(*w, model.width)).collect();
+                itertools::repeat_n(row, model.height)
             })
-            .collect()
-    }
-
-    fn make_wide(self) -> Map<Wide> {
-        let wide_map = self
-            .map
-            .into_iter()
-            .map(|row| {
-                row.into_iter()
-                    .flat_map(|c| match c {
-                        '#' => ['#', '#'],
-                        'O' => ['[', ']'],
-                        '.' => ['.', '.'],
-                        '@' => ['@', '.'],
-                        _ => unreachable!(),
-                    })
-                    .collect()
+            .collect();
+        let boxes = self
+            .boxes
+            .iter()
+            .map(|b| Crate {
+                pos: (b.pos.0 * model.width, b.pos.1 * model.height),
             })
             .collect();
-        Map {
-            map: wide_map,
-            moves: self.moves,
-            _state: PhantomData::<Wide>,
+        let robot = (self.robot.0 * model.width, self.robot.1 * model.height);
+        let model = BoxModel {
+            width: self.model.width * model.width,
+            height: self.model.height * model.height,
+        };
+        Self::new(model, walls, boxes, self.moves, robot)
+    }
+
+    fn cells(&self, id: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (x, y) = self.boxes[id].pos;
+        let (width, height) = (self.model.width, self.model.height);
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| (x, y)))
+    }
+
+    fn occupy(&mut self, id: usize, value: Option<usize>) {
+        for (x, y) in self.cells(id).collect::<Vec<_>>() {
+            self.occupancy[y][x] = value;
         }
     }
-}
 
-impl Map<Wide> {
-    fn resolve(&mut self) {
-        let mut pos = self.start_pos();
-        while let Some(mov) = self.moves.pop_front() {
-            debug!("\n{self}");
-            let (x, y) = (pos.0 as isize, pos.1 as isize);
-
-            let mut can_swap = true;
-            let mut swaps = Vec::new();
-
-            let mut stack = VecDeque::new();
-            stack.push_front((x, y));
-
-            while let Some((x, y)) = stack.pop_back() {
-                let entry = self.get_map_entry((x, y));
-                let (new_x, new_y) = new_pos((x, y), mov);
-                let new_entry = self.get_map_entry((new_x, new_y));
-                debug!("pos:({x}, {y}) ({entry}) new_pos:({new_x}, {new_y}) ({new_entry}) mov:{mov}");
-                if new_entry == '#' {
-                    can_swap = false;
-                    break;
-                }
-                if !swaps.contains(&((x, y), (new_x, new_y))) {
-                    debug!("adding swap: {:?}", ((x, y), (new_x, new_y)));
-                    swaps.push(((x, y), (new_x, new_y)));
+    fn is_wall(&self, (x, y): (isize, isize)) -> bool {
+        if x < 0 || y < 0 {
+            return true;
+        }
+        self.walls
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(true)
+    }
+
+    fn box_glyph(&self, id: usize, x: usize) -> char {
+        let (left, _) = self.boxes[id].pos;
+        match (self.model.width, x - left) {
+            (1, _) => 'O',
+            (_, 0) => '[',
+            (w, dx) if dx == w - 1 => ']',
+            _ => '=',
+        }
+    }
+
+    // Apply the next queued move, if any.
+    fn step(&mut self) -> Option<Step> {
+        let mov = self.moves.pop_front()?;
+        Some(self.apply(mov))
+    }
+
+    // Move the robot once, pushing any boxes in the way.
+    //
+    // Starting from the box in front of the robot, every box that would overlap with a box being
+    // pushed gets pushed as well. If any of them would end up in a wall, nothing moves.
+    fn apply(&mut self, mov: char) -> Step {
+        let pos = self.robot;
+        let target = new_pos((pos.0 as isize, pos.1 as isize), mov);
+        let blocked = Step {
+            mov,
+            robot: (pos, pos),
+            boxes: Vec::new(),
+        };
+        debug!("pos:{pos:?} target:{target:?} mov:{mov}");
+        if self.is_wall(target) {
+            return blocked;
+        }
+
+        let mut pushed = Vec::new();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        if let Some(id) = self.occupancy[target.1 as usize][target.0 as usize] {
+            seen.insert(id);
+            queue.push_back(id);
+        }
+        while let Some(id) = queue.pop_front() {
+            for (x, y) in self.cells(id) {
+                let next = new_pos((x as isize, y as isize), mov);
+                if self.is_wall(next) {
+                    debug!("box {id} at {:?} blocked by wall at {next:?}", self.boxes[id].pos);
+                    return blocked;
                 }
-                match (new_entry, mov) {
-                    (']', '^' | 'v') => {
-                        // v
-                        // [|]
-                        // ^
-                        debug!("adding stack: {:?} {:?}", (new_x - 1, new_y), (new_x, new_y));
-                        stack.push_front((new_x - 1, new_y));
-                        stack.push_front((new_x, new_y));
-                    }
-                    ('[', '^' | 'v') => {
-                        //   v
-                        // [|]
-                        //   ^
-                        debug!("adding stack: {:?} {:?}", (new_x + 1, new_y), (new_x, new_y));
-                        stack.push_front((new_x + 1, new_y));
-                        stack.push_front((new_x, new_y));
+                if let Some(other) = self.occupancy[next.1 as usize][next.0 as usize] {
+                    if seen.insert(other) {
+                        queue.push_back(other);
                     }
-                    ('[' | ']', '<' | '>') => {
-                        stack.push_front((new_x, new_y));
-                    }
-                    ('.', '<' | '>') => {
-                        break;
-                    }
-                    ('.', _) => {}
-                    _ => unreachable!(),
                 }
             }
-            if can_swap {
-                while let Some((left, right)) = swaps.pop() {
-                    debug!("swapping ({left:?}) ({right:?})");
-                    self.swap(left, right);
-                }
-                let new_pos = new_pos((pos.0 as isize, pos.1 as isize), mov);
-                pos = (new_pos.0 as usize, new_pos.1 as usize);
-            }
+            pushed.push(id);
+        }
+
+        for id in pushed.iter() {
+            self.occupy(*id, None);
+        }
+        let mut boxes = Vec::new();
+        for id in pushed.iter() {
+            let from = self.boxes[*id].pos;
+            let to = as_usize(new_pos((from.0 as isize, from.1 as isize), mov));
+            self.boxes[*id].pos = to;
+            self.occupy(*id, Some(*id));
+            boxes.push((from, to));
+        }
+        self.robot = as_usize(target);
+        Step {
+            mov,
+            robot: (pos, self.robot),
+            boxes,
         }
     }
 
-    // This warehouse also uses GPS to locate the boxes. For these larger boxes, distances are measured
-    // from the edge of the map to the closest edge of the box in question. So, the box shown below has
-    // a distance of 1 from the top edge of the map and 5 from the left edge of the map, resulting in a
-    // GPS coordinate of 100 * 1 + 5 = 105.
+    // The GPS coordinate of a box is 100 times its distance from the top edge of the map plus its
+    // distance from the left edge of the map. For larger boxes, distances are measured from the
+    // edge of the map to the closest edge of the box in question. So, the box shown below has a
+    // distance of 1 from the top edge of the map and 5 from the left edge of the map, resulting in
+    // a GPS coordinate of 100 * 1 + 5 = 105.
     //
     //   ##########
     //   ##...[]...
     //   ##........
     fn gps_coordinates(&self) -> Vec<usize> {
-        self.map
-            .iter()
-            .enumerate()
-            .flat_map(|(y, row)| {
-                row.iter()
-                    .enumerate()
-                    .tuple_windows()
-                    .filter(|((_, c1), (_, c2))| *c1 == &'[' && *c2 == &']')
-                    .map(move |((x1, _), _)| (y * 100) + x1)
-            })
-            .collect()
+        self.boxes.iter().map(|b| (b.pos.1 * 100) + b.pos.0).collect()
+    }
+
+    fn image(&self) -> Image {
+        let palette = Palette::new(render::BACKGROUND)
+            .with('#', render::WALL)
+            .with('@', render::ACTOR)
+            .with('O', render::OBJECT)
+            .with('[', render::OBJECT)
+            .with('=', render::OBJECT)
+            .with(']', render::OBJECT);
+        Image::from_text(&self.to_string(), &palette)
+    }
+
+    // The warehouse with the boxes moved by `step` highlighted.
+    fn pushed_image(&self, step: &Step) -> Image {
+        let mut image = self.image();
+        for (_, (x, y)) in step.boxes.iter() {
+            for dy in 0..self.model.height {
+                for dx in 0..self.model.width {
+                    image.set((x + dx, y + dy), render::HIGHLIGHT);
+                }
+            }
+        }
+        image
     }
 }
 
@@ -299,3 +485,7 @@ fn new_pos((x, y): (isize, isize), mov: char) -> (isize, isize) {
         _ => unreachable!(),
     }
 }
+
+fn as_usize((x, y): (isize, isize)) -> (usize, usize) {
+    (x as usize, y as usize)
+}
@@ -0,0 +1,140 @@
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, terminal};
+
+use super::{BoxModel, Map};
+
+const HELP: &str = "arrows/wasd: move  n: next input move  u: undo  t: toggle scale  r: reset  q: quit";
+
+struct Game {
+    initial: Map,
+    // footprint used when the scaled warehouse is toggled on
+    model: BoxModel,
+    scaled: bool,
+    // the input moves not played yet are still queued in the warehouse
+    warehouse: Map,
+    history: Vec<(Map, Option<char>)>,
+    last_move: Option<char>,
+}
+
+impl Game {
+    fn new(initial: Map, model: BoxModel, scaled: bool) -> Self {
+        let warehouse = if scaled {
+            initial.clone().scale(model)
+        } else {
+            initial.clone()
+        };
+        Self {
+            initial,
+            model,
+            scaled,
+            warehouse,
+            history: Vec::new(),
+            last_move: None,
+        }
+    }
+
+    fn play(&mut self, mov: char) {
+        self.history.push((self.warehouse.clone(), self.last_move));
+        self.warehouse.apply(mov);
+        self.last_move = Some(mov);
+    }
+
+    fn play_next(&mut self) {
+        let Some(mov) = self.warehouse.moves.front().copied() else {
+            return;
+        };
+        self.history.push((self.warehouse.clone(), self.last_move));
+        self.warehouse.step();
+        self.last_move = Some(mov);
+    }
+
+    fn undo(&mut self) {
+        if let Some((warehouse, last_move)) = self.history.pop() {
+            self.warehouse = warehouse;
+            self.last_move = last_move;
+        }
+    }
+
+    // Toggling restarts from the initial warehouse: a scaled map can't always be narrowed back.
+    fn toggle(&mut self) {
+        *self = Self::new(self.initial.clone(), self.model, !self.scaled);
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.initial.clone(), self.model, self.scaled);
+    }
+
+    fn render(&self, out: &mut impl Write) -> Result<()> {
+        execute!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+        // raw mode doesn't translate `\n` into `\r\n`
+        for line in self.warehouse.to_string().lines() {
+            write!(out, "{line}\r\n")?;
+        }
+        let mode = format!("{}x{}", self.warehouse.model.width, self.warehouse.model.height);
+        let last_move = self.last_move.map(String::from).unwrap_or(String::from("-"));
+        write!(
+            out,
+            "\r\nmode:{mode} moves:{} last:{last_move} queued:{} gps:{}\r\n{HELP}\r\n",
+            self.history.len(),
+            self.warehouse.moves.len(),
+            self.warehouse.gps_coordinates().iter().sum::<usize>()
+        )?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+// Restores the terminal even when the game loop bails out early.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode().context("enabling raw mode")?;
+        execute!(std::io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        _ = execute!(std::io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        _ = terminal::disable_raw_mode();
+    }
+}
+
+pub(super) fn play(initial: Map, model: BoxModel, scaled: bool) -> Result<()> {
+    let mut game = Game::new(initial, model, scaled);
+
+    let _terminal = RawTerminal::enter()?;
+    let mut stdout = std::io::stdout();
+    loop {
+        game.render(&mut stdout)?;
+
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read().context("reading terminal event")?
+        else {
+            continue;
+        };
+        match code {
+            KeyCode::Up | KeyCode::Char('w') => game.play('^'),
+            KeyCode::Down | KeyCode::Char('s') => game.play('v'),
+            KeyCode::Left | KeyCode::Char('a') => game.play('<'),
+            KeyCode::Right | KeyCode::Char('d') => game.play('>'),
+            KeyCode::Char('n') => game.play_next(),
+            KeyCode::Char('u') | KeyCode::Backspace => game.undo(),
+            KeyCode::Char('t') => game.toggle(),
+            KeyCode::Char('r') => game.reset(),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -1,16 +1,31 @@
 use std::cell::RefCell;
 use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
-use std::fmt::Debug;
+use std::fmt::{Debug, Write};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
 
-use anyhow::Result;
+use adventofcode::animation::{AnimationArgs, Recorder};
+use adventofcode::render::{self, Image};
+use anyhow::{Context, Result};
 use tracing::{debug, info};
 
 #[derive(Debug, clap::Args)]
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Save an image of the maze and the best path found to this file (.png or .ppm).
+    #[arg(long, global = true)]
+    render: Option<PathBuf>,
+
+    /// Write the graph of junctions, with the cost of every corridor and the best path, to this
+    /// Graphviz file.
+    #[arg(long, global = true)]
+    dot: Option<PathBuf>,
+
+    #[command(flatten)]
+    animation: AnimationArgs,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -25,18 +40,28 @@ pub enum Commands {
 impl Args {
     pub fn run(self, input: String) -> Result<()> {
         match self.command {
-            Commands::Part1 => part1(input),
+            Commands::Part1 => part1(input, self.render.as_deref(), self.dot.as_deref(), &self.animation),
             Commands::Part2 => part2(input),
         }
     }
 }
 
-fn part1(input: String) -> Result<()> {
+fn part1(input: String, render: Option<&Path>, dot: Option<&Path>, animation: &AnimationArgs) -> Result<()> {
     let maze = Maze::from_str(&input).unwrap();
     info!("{maze:?}");
 
-    let shortest = maze.dijkstra();
+    let mut recorder = animation.recorder()?;
+    let (shortest, path) = maze.dijkstra(&mut recorder);
+    recorder.finish(|| maze.image(&path))?;
+
     println!("{shortest}");
+
+    if let Some(file) = render {
+        maze.image(&path).save(file)?;
+    }
+    if let Some(file) = dot {
+        std::fs::write(file, maze.dot(&path)).with_context(|| format!("writing dot file '{}'", file.display()))?;
+    }
     Ok(())
 }
 
@@ -150,10 +175,24 @@ impl PartialEq for NodeDistance {
 
 impl Eq for NodeDistance {}
 
+// A stretch of maze between two junctions, the tiles where the reindeer has a choice to make:
+// forks, dead ends, and the start and end tiles. `cells` excludes `from` and ends with `to`.
+#[derive(Debug)]
+struct Corridor {
+    from: (usize, usize),
+    to: (usize, usize),
+    cells: Vec<(usize, usize)>,
+    // moving and turning within the corridor, turns at its ends depend on where the reindeer
+    // comes from and goes next
+    cost: usize,
+}
+
 #[derive(Debug)]
 struct Maze {
     nodes: HashMap<(usize, usize), Rc<RefCell<Node>>>,
     graph: Graph,
+    width: usize,
+    height: usize,
 }
 
 impl FromStr for Maze {
@@ -200,6 +239,8 @@ impl FromStr for Maze {
         Ok(Maze {
             nodes,
             graph: Graph { root },
+            width: s.lines().map(str::len).max().unwrap_or(0),
+            height: s.lines().count(),
         })
     }
 }
@@ -249,7 +290,8 @@ impl Maze {
         }
     }
 
-    fn dijkstra(&self) -> usize {
+    // Returns the lowest score along with the path that reaches it, from start to end.
+    fn dijkstra(&self, recorder: &mut Recorder) -> (usize, Vec<(usize, usize)>) {
         let mut distances: HashMap<(usize, usize), Option<usize>> = self
             .nodes
             .iter()
@@ -274,6 +316,7 @@ impl Maze {
         while let Some(current) = heap.pop() {
             let node = self.nodes.get(&current.pos).unwrap().borrow();
             visited.insert(node.datum.pos);
+            recorder.record(|| self.search_image(&visited, current.pos));
 
             if matches!(node.datum.kind, Kind::End) {
                 end_pos = Some(current.pos);
@@ -318,12 +361,135 @@ impl Maze {
             pos = *left;
         }
 
-        let mut path_str = format!("{:?}", path.pop().unwrap());
-        for pos in path.iter().rev() {
+        path.reverse();
+
+        let mut path_str = format!("{:?}", path[0]);
+        for pos in path.iter().skip(1) {
             path_str += &format!(" -> {pos:?}");
         }
         info!("path: {path_str}");
 
-        distances.get(&end_pos.unwrap()).unwrap().unwrap()
+        (distances.get(&end_pos.unwrap()).unwrap().unwrap(), path)
+    }
+
+    fn image(&self, path: &[(usize, usize)]) -> Image {
+        let mut image = Image::new(self.width, self.height, render::WALL);
+        for (pos, node) in self.nodes.iter() {
+            let colour = match node.borrow().datum.kind {
+                Kind::Start => render::ACTOR,
+                Kind::End => render::GREEN,
+                _ if path.contains(pos) => render::HIGHLIGHT,
+                _ => render::BACKGROUND,
+            };
+            image.set(*pos, colour);
+        }
+        image
+    }
+
+    // Cells already settled by the search, with the one being expanded on top.
+    fn search_image(&self, visited: &HashSet<(usize, usize)>, current: (usize, usize)) -> Image {
+        let mut image = self.image(&[]);
+        for pos in visited {
+            if image.get(*pos) == render::BACKGROUND {
+                image.set(*pos, render::VISITED);
+            }
+        }
+        image.set(current, render::HIGHLIGHT);
+        image
+    }
+
+    fn is_junction(node: &Node) -> bool {
+        !matches!(node.datum.kind, Kind::Path) || node.edges.len() != 2
+    }
+
+    // Follow every way out of every junction up to the next junction. Each corridor is found once
+    // from each end, only the first one is kept.
+    fn corridors(&self) -> Vec<Corridor> {
+        let mut junctions: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| Self::is_junction(&node.borrow()))
+            .collect();
+        junctions.sort_by_key(|(pos, _)| (pos.1, pos.0));
+
+        let mut seen = HashSet::new();
+        let mut corridors = Vec::new();
+        for (from, junction) in junctions {
+            for (next, dir) in junction.borrow().edges.iter() {
+                let (mut prev, mut node, mut dir, mut cost) = (*from, next.clone(), *dir, 1);
+                let mut cells = Vec::new();
+                loop {
+                    let pos = node.borrow().datum.pos;
+                    cells.push(pos);
+                    if Self::is_junction(&node.borrow()) {
+                        break;
+                    }
+                    let (next, next_dir) = node
+                        .borrow()
+                        .edges
+                        .iter()
+                        .find(|(n, _)| n.borrow().datum.pos != prev)
+                        .cloned()
+                        .unwrap();
+                    cost += if next_dir == dir { 1 } else { 1001 };
+                    (prev, node, dir) = (pos, next, next_dir);
+                }
+
+                let to = *cells.last().unwrap();
+                let before_to = if cells.len() > 1 { cells[cells.len() - 2] } else { *from };
+                let ends = [(*from, cells[0]), (to, before_to)];
+                if seen.insert((ends[0].min(ends[1]), ends[0].max(ends[1]))) {
+                    corridors.push(Corridor {
+                        from: *from,
+                        to,
+                        cells,
+                        cost,
+                    });
+                }
+            }
+        }
+        corridors
+    }
+
+    // Junctions carry their maze coordinates, `neato -n2` keeps them in place while `dot` lays the
+    // graph out freely.
+    fn dot(&self, path: &[(usize, usize)]) -> String {
+        let on_path: HashSet<_> = path.iter().collect();
+        let corridors = self.corridors();
+        let mut junctions: Vec<_> = corridors.iter().flat_map(|c| [c.from, c.to]).collect();
+        junctions.sort_by_key(|(x, y)| (*y, *x));
+        junctions.dedup();
+
+        let mut out = String::new();
+        writeln!(out, "graph maze {{").unwrap();
+        writeln!(out, "  node [shape=circle, fontsize=8, width=0.3, fixedsize=true];").unwrap();
+        writeln!(out, "  edge [fontsize=8];").unwrap();
+        for (x, y) in junctions {
+            let style = match self.nodes[&(x, y)].borrow().datum.kind {
+                Kind::Start => r#", label="S", style=filled, fillcolor=firebrick"#,
+                Kind::End => r#", label="E", style=filled, fillcolor=forestgreen"#,
+                Kind::Path if on_path.contains(&(x, y)) => r#", style=filled, fillcolor=gold"#,
+                Kind::Path => "",
+            };
+            writeln!(
+                out,
+                r#"  "{x},{y}" [pos="{},{}"{style}];"#,
+                x * 40,
+                (self.height - y) * 40
+            )
+            .unwrap();
+        }
+        for corridor in corridors {
+            let best = on_path.contains(&corridor.from) && corridor.cells.iter().all(|c| on_path.contains(c));
+            let style = if best { r#", color=gold, penwidth=3"# } else { "" };
+            writeln!(
+                out,
+                r#"  "{},{}" -- "{},{}" [label="{}"{style}];"#,
+                corridor.from.0, corridor.from.1, corridor.to.0, corridor.to.1, corridor.cost
+            )
+            .unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
     }
 }
@@ -0,0 +1,209 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use tracing::info;
+
+use super::generate::{generate, parse_day};
+use super::reference::{self, Solver};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Day to test (e.g. `6`, `06` or `day06`).
+    #[arg(value_parser = parse_day)]
+    day: u32,
+
+    /// Only test this part.
+    #[arg(long)]
+    part: Option<u32>,
+
+    /// Number of generated inputs to test.
+    #[arg(long, default_value_t = 100)]
+    cases: u64,
+
+    /// Seed of the first generated input, the next ones use the following seeds.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Size of the generated inputs (see `generate`).
+    #[arg(long, default_value_t = 8)]
+    size: usize,
+
+    /// Stop solutions running for longer than this on an input, which is then reported as a
+    /// timeout (e.g. `10s`).
+    #[arg(long, value_parser = crate::parse_duration)]
+    timeout: Option<Duration>,
+}
+
+impl Args {
+    pub fn run(self) -> Result<()> {
+        let parts: Vec<u32> = self.part.map(|p| vec![p]).unwrap_or(vec![1, 2]);
+        let mut failures = 0;
+        for part in parts {
+            let Some(reference) = reference::solver(self.day, part) else {
+                println!("day {:02} part {part}: no reference solver", self.day);
+                continue;
+            };
+            for command in commands(self.day, part) {
+                if !self.check(part, reference, command)? {
+                    failures += 1;
+                }
+            }
+        }
+        if failures > 0 {
+            return Err(anyhow!("{failures} solution(s) disagree with the reference"));
+        }
+        Ok(())
+    }
+
+    // Stops at the first mismatch, which is shrunk and printed.
+    fn check(&self, part: u32, reference: Solver, command: &str) -> Result<bool> {
+        for seed in self.seed..self.seed + self.cases {
+            let input = generate(self.day, seed, self.size)?;
+            let expected = reference(&input)
+                .with_context(|| format!("reference rejected the input generated with seed {seed}"))?;
+            let actual = optimised(self.day, command, &input, self.timeout);
+            info!("seed:{seed} expected:{expected} actual:{actual:?}");
+            if actual.as_ref().is_ok_and(|a| *a == expected.to_string()) {
+                continue;
+            }
+
+            let input = shrink(&input, |candidate| {
+                let Ok(expected) = reference(candidate) else {
+                    return false;
+                };
+                optimised(self.day, command, candidate, self.timeout).map_or(true, |a| a != expected.to_string())
+            });
+            let expected = reference(&input)?;
+            let (outcome, actual) = match optimised(self.day, command, &input, self.timeout) {
+                Ok(actual) => ("mismatch", actual),
+                Err(err) if err.is::<crate::TimedOut>() => ("TIMEOUT", format!("{err:#}")),
+                Err(err) => ("failure", format!("{err:#}")),
+            };
+            println!(
+                "day {:02} part {part} ({command}): {outcome} with seed {seed}, shrunk to:",
+                self.day
+            );
+            println!("{input}");
+            println!("reference: {expected}");
+            println!("optimised: {actual}");
+            return Ok(false);
+        }
+        println!("day {:02} part {part} ({command}): {} cases ok", self.day, self.cases);
+        Ok(true)
+    }
+}
+
+pub(super) fn commands(day: u32, part: u32) -> Vec<&'static str> {
+    match (day, part) {
+        (1, 1) => vec!["part1-sort", "part1-binary-heap"],
+        (_, 1) => vec!["part1"],
+        _ => vec!["part2"],
+    }
+}
+
+// Solutions are run as a child process of this binary, so that a panic is reported like any other
+// wrong answer. The answer is the first line printed.
+fn optimised(day: u32, command: &str, input: &str, timeout: Option<Duration>) -> Result<String> {
+    let mut solution = Command::new(std::env::current_exe()?);
+    if let Some(timeout) = timeout {
+        solution.arg("--timeout").arg(format!("{}ms", timeout.as_millis()));
+    }
+    let mut child = solution
+        .args(["2024", &format!("day{day:02}"), command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("running solution")?;
+    // the solution may exit before reading all of its input
+    _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    let output = child.wait_with_output().context("running solution")?;
+
+    if output.status.code() == Some(crate::TIMEOUT_STATUS) {
+        return Err(crate::TimedOut(timeout.unwrap_or_default()).into());
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut lines = stderr.lines();
+        let reason = match lines.find(|l| l.contains("panicked at")) {
+            Some(panic) => format!("{panic} {}", lines.next().unwrap_or_default()),
+            None => stderr.lines().next().unwrap_or_default().to_string(),
+        };
+        return Err(anyhow!("{}: {reason}", output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or_default().trim().to_string())
+}
+
+// Greedily remove parts of the input for as long as it keeps failing: chunks of lines, then
+// columns for grids, and tokens or characters when a single line is left.
+fn shrink(input: &str, fails: impl Fn(&str) -> bool) -> String {
+    let mut input = input.to_string();
+    'shrink: loop {
+        for candidate in candidates(&input) {
+            if fails(&candidate) {
+                input = candidate;
+                continue 'shrink;
+            }
+        }
+        return input;
+    }
+}
+
+fn candidates(input: &str) -> Vec<String> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut candidates: Vec<String> = removals(&lines)
+        .into_iter()
+        .map(|lines| lines.iter().map(|l| format!("{l}\n")).collect())
+        .collect();
+
+    let width = lines.first().map(|l| l.chars().count()).unwrap_or(0);
+    if lines.len() > 1 && lines.iter().all(|l| l.chars().count() == width && !l.contains(' ')) {
+        let columns: Vec<usize> = (0..width).collect();
+        for kept in removals(&columns) {
+            let candidate = lines
+                .iter()
+                .map(|l| {
+                    l.chars()
+                        .enumerate()
+                        .filter(|(x, _)| kept.contains(x))
+                        .map(|(_, c)| c)
+                        .collect()
+                })
+                .map(|l: String| l + "\n")
+                .collect();
+            candidates.push(candidate);
+        }
+    }
+
+    if let [line] = lines[..] {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() > 1 {
+            candidates.extend(removals(&tokens).into_iter().map(|t| t.join(" ") + "\n"));
+        } else {
+            let chars: Vec<char> = line.chars().collect();
+            candidates.extend(
+                removals(&chars)
+                    .into_iter()
+                    .map(|c| c.iter().collect::<String>() + "\n"),
+            );
+        }
+    }
+    candidates
+}
+
+// Every way of removing one chunk of units, from halves down to single units.
+fn removals<T: Clone>(units: &[T]) -> Vec<Vec<T>> {
+    let mut removals = Vec::new();
+    let mut chunk = units.len() / 2;
+    while chunk > 0 {
+        for start in (0..units.len()).step_by(chunk) {
+            let end = (start + chunk).min(units.len());
+            removals.push([&units[..start], &units[end..]].concat());
+        }
+        chunk /= 2;
+    }
+    removals
+}
@@ -0,0 +1,444 @@
+use std::fmt::Write;
+
+use adventofcode::rng::Rng;
+use anyhow::{anyhow, Context, Result};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Day to generate an input for (e.g. `6`, `06` or `day06`).
+    #[arg(value_parser = parse_day)]
+    day: u32,
+
+    /// Seed of the random generator: the same seed and size always produce the same input.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Size of the input: number of lines or records, or side of the grid for map based days.
+    #[arg(long, default_value_t = 10)]
+    size: usize,
+}
+
+impl Args {
+    pub fn run(self) -> Result<()> {
+        print!("{}", generate(self.day, self.seed, self.size)?);
+        Ok(())
+    }
+}
+
+pub(super) fn parse_day(s: &str) -> Result<u32> {
+    let day = s.strip_prefix("day").unwrap_or(s);
+    day.parse().with_context(|| format!("invalid day '{s}'"))
+}
+
+pub fn generate(day: u32, seed: u64, size: usize) -> Result<String> {
+    let mut rng = Rng::new(seed);
+    let size = size.max(1);
+    let input = match day {
+        1 => location_lists(&mut rng, size),
+        2 => reports(&mut rng, size),
+        3 => corrupted_memory(&mut rng, size),
+        4 => word_grid(&mut rng, size),
+        5 => ordering_rules(&mut rng, size),
+        6 => guard_map(&mut rng, size),
+        7 => equations(&mut rng, size),
+        8 => antenna_map(&mut rng, size),
+        9 => disk_map(&mut rng, size),
+        10 => trail_map(&mut rng, size),
+        11 => stones(&mut rng, size),
+        12 => garden(&mut rng, size),
+        13 => claw_machines(&mut rng, size),
+        14 => robots(&mut rng, size),
+        15 => warehouse(&mut rng, size),
+        16 => maze(&mut rng, size),
+        _ => return Err(anyhow!("no generator for day {day}")),
+    };
+    Ok(input)
+}
+
+fn grid_to_string(grid: &[Vec<char>]) -> String {
+    grid.iter().map(|row| row.iter().collect::<String>() + "\n").collect()
+}
+
+// Day 01: two columns of location IDs. Half of the right column is picked from the left one so
+// that the similarity score isn't always zero.
+fn location_lists(rng: &mut Rng, size: usize) -> String {
+    let left: Vec<i64> = (0..size).map(|_| rng.between(10_000, 99_999)).collect();
+    let mut out = String::new();
+    for l in left.iter() {
+        let r = if rng.chance(0.5) {
+            *rng.choose(&left)
+        } else {
+            rng.between(10_000, 99_999)
+        };
+        writeln!(out, "{l}   {r}").unwrap();
+    }
+    out
+}
+
+// Day 02: mostly monotonic reports with the occasional bad level.
+fn reports(rng: &mut Rng, size: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..size {
+        let direction = if rng.chance(0.5) { 1 } else { -1 };
+        let mut level = rng.between(10, 90);
+        let mut levels = vec![level];
+        for _ in 1..rng.between(5, 8) {
+            let step = if rng.chance(0.85) {
+                direction * rng.between(1, 3)
+            } else {
+                rng.between(-5, 5)
+            };
+            level = (level + step).abs().max(1);
+            levels.push(level);
+        }
+        writeln!(
+            out,
+            "{}",
+            levels.iter().map(i64::to_string).collect::<Vec<_>>().join(" ")
+        )
+        .unwrap();
+    }
+    out
+}
+
+// Day 03: `size` instructions, valid or not, buried in noise.
+fn corrupted_memory(rng: &mut Rng, size: usize) -> String {
+    const NOISE: &[&str] = &[
+        "mul(", ")", ",", "(", "[]", "%", "*", "!", "@", "^", " ", "do", "don", "mul",
+    ];
+    const DECOYS: &[&str] = &[
+        "from()",
+        "what()",
+        "mul (2,4)",
+        "mul[3,7]",
+        "mul(4*",
+        "mul(6,9!",
+        "?(12,34)",
+    ];
+    let mut out = String::new();
+    for i in 0..size {
+        for _ in 0..rng.between(0, 4) {
+            write!(out, "{}", rng.choose(NOISE)).unwrap();
+        }
+        if rng.chance(0.2) {
+            write!(out, "{}", rng.choose(DECOYS)).unwrap();
+        }
+        match rng.below(10) {
+            0 => out.push_str("do()"),
+            1 => out.push_str("don't()"),
+            // operands with more than three digits are not valid instructions
+            2 => write!(out, "mul({},{})", rng.between(1, 9999), rng.between(1000, 9999)).unwrap(),
+            _ => write!(out, "mul({},{})", rng.between(0, 999), rng.between(0, 999)).unwrap(),
+        }
+        if i % 20 == 19 {
+            out.push('\n');
+        }
+    }
+    out.push('\n');
+    out
+}
+
+// Day 04: a square grid of the letters of XMAS.
+fn word_grid(rng: &mut Rng, size: usize) -> String {
+    let grid: Vec<Vec<char>> = (0..size)
+        .map(|_| (0..size).map(|_| *rng.choose(&['X', 'M', 'A', 'S'])).collect())
+        .collect();
+    grid_to_string(&grid)
+}
+
+// Day 05: a total order over some pages, given as every pairwise rule, followed by `size` updates
+// of which about half are correctly ordered.
+fn ordering_rules(rng: &mut Rng, size: usize) -> String {
+    let mut pages: Vec<i64> = (10..=99).collect();
+    rng.shuffle(&mut pages);
+    pages.truncate(size.clamp(3, pages.len()));
+
+    let mut rules = Vec::new();
+    for (i, before) in pages.iter().enumerate() {
+        for after in pages[i + 1..].iter() {
+            rules.push(format!("{before}|{after}"));
+        }
+    }
+    rng.shuffle(&mut rules);
+
+    let mut out = rules.join("\n") + "\n\n";
+    let max_len = pages.len().min(23);
+    let max_len = if max_len.is_multiple_of(2) {
+        max_len - 1
+    } else {
+        max_len
+    };
+    for _ in 0..size {
+        // updates have an odd length, so that they have a middle page
+        let len = 2 * rng.between(1, max_len as i64 / 2) as usize + 1;
+        let mut update: Vec<usize> = (0..pages.len()).collect();
+        rng.shuffle(&mut update);
+        update.truncate(len);
+        if rng.chance(0.5) {
+            update.sort();
+        }
+        let update: Vec<_> = update.iter().map(|i| pages[*i].to_string()).collect();
+        writeln!(out, "{}", update.join(",")).unwrap();
+    }
+    out
+}
+
+// Day 06: obstructions and a guard facing up. Maps where the guard never leaves are rerolled.
+fn guard_map(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(2);
+    loop {
+        let mut grid: Vec<Vec<char>> = (0..size)
+            .map(|_| (0..size).map(|_| if rng.chance(0.1) { '#' } else { '.' }).collect())
+            .collect();
+        let guard = (rng.below(size), rng.below(size));
+        grid[guard.1][guard.0] = '^';
+        if guard_leaves(&grid, guard) {
+            return grid_to_string(&grid);
+        }
+    }
+}
+
+fn guard_leaves(grid: &[Vec<char>], (mut x, mut y): (usize, usize)) -> bool {
+    let size = grid.len() as isize;
+    let (mut dx, mut dy) = (0, -1);
+    let mut seen = std::collections::HashSet::new();
+    while seen.insert((x, y, dx, dy)) {
+        let (nx, ny) = (x as isize + dx, y as isize + dy);
+        if nx < 0 || ny < 0 || nx >= size || ny >= size {
+            return true;
+        }
+        if grid[ny as usize][nx as usize] == '#' {
+            (dx, dy) = (-dy, dx);
+        } else {
+            (x, y) = (nx as usize, ny as usize);
+        }
+    }
+    false
+}
+
+// Day 07: equations whose test value is, most of the time, reachable with +, * and ||.
+fn equations(rng: &mut Rng, size: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..size {
+        let nums: Vec<u64> = (0..rng.between(2, 8)).map(|_| rng.between(1, 99) as u64).collect();
+        let test_value = if rng.chance(0.6) {
+            nums[1..].iter().fold(nums[0], |acc, n| match rng.below(3) {
+                0 => acc + n,
+                1 => acc * n,
+                _ => acc * 10u64.pow(n.ilog10() + 1) + n,
+            })
+        } else {
+            nums.iter().sum::<u64>() * rng.between(1, 50) as u64
+        };
+        let nums: Vec<_> = nums.iter().map(u64::to_string).collect();
+        writeln!(out, "{test_value}: {}", nums.join(" ")).unwrap();
+    }
+    out
+}
+
+// Day 08: a few antennas for each of a handful of frequencies.
+fn antenna_map(rng: &mut Rng, size: usize) -> String {
+    const FREQUENCIES: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    let size = size.max(2);
+    let mut grid = vec![vec!['.'; size]; size];
+    for _ in 0..(1 + size / 4) {
+        let frequency = *rng.choose(FREQUENCIES) as char;
+        for _ in 0..rng.between(2, 4) {
+            let (x, y) = (rng.below(size), rng.below(size));
+            if grid[y][x] == '.' {
+                grid[y][x] = frequency;
+            }
+        }
+    }
+    grid_to_string(&grid)
+}
+
+// Day 09: a dense disk map that starts and ends with a file.
+fn disk_map(rng: &mut Rng, size: usize) -> String {
+    let len = if size.is_multiple_of(2) { size + 1 } else { size };
+    let mut out: String = (0..len)
+        .map(|i| {
+            let digit = if i % 2 == 0 {
+                rng.between(1, 9)
+            } else {
+                rng.between(0, 9)
+            };
+            char::from_digit(digit as u32, 10).unwrap()
+        })
+        .collect();
+    out.push('\n');
+    out
+}
+
+// Day 10: random heights with a few hiking trails carved in, so that trailheads lead somewhere.
+fn trail_map(rng: &mut Rng, size: usize) -> String {
+    let mut grid: Vec<Vec<char>> = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| char::from_digit(rng.below(10) as u32, 10).unwrap())
+                .collect()
+        })
+        .collect();
+    for _ in 0..(size / 2).max(1) {
+        let (mut x, mut y) = (rng.below(size), rng.below(size));
+        let mut trail = Vec::new();
+        for h in 0..10 {
+            grid[y][x] = char::from_digit(h, 10).unwrap();
+            trail.push((x as isize, y as isize));
+            let neighbours: Vec<_> = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                .iter()
+                .map(|(dx, dy)| (x as isize + dx, y as isize + dy))
+                .filter(|(nx, ny)| *nx >= 0 && *ny >= 0 && (*nx as usize) < size && (*ny as usize) < size)
+                .filter(|n| !trail.contains(n))
+                .collect();
+            if neighbours.is_empty() {
+                break;
+            }
+            let (nx, ny) = *rng.choose(&neighbours);
+            (x, y) = (nx as usize, ny as usize);
+        }
+    }
+    grid_to_string(&grid)
+}
+
+// Day 11: stones with an even mix of zeroes, small and large numbers.
+fn stones(rng: &mut Rng, size: usize) -> String {
+    let stones: Vec<_> = (0..size)
+        .map(|_| match rng.below(3) {
+            0 => 0,
+            1 => rng.between(1, 999),
+            _ => rng.between(1_000, 9_999_999),
+        })
+        .map(|s| s.to_string())
+        .collect();
+    stones.join(" ") + "\n"
+}
+
+// Day 12: plots mostly copy their left or top neighbour, which grows irregular regions.
+fn garden(rng: &mut Rng, size: usize) -> String {
+    let plants = (2 + size / 3).min(26);
+    let mut grid = vec![vec!['A'; size]; size];
+    for y in 0..size {
+        for x in 0..size {
+            grid[y][x] = match rng.below(10) {
+                0..=3 if x > 0 => grid[y][x - 1],
+                4..=7 if y > 0 => grid[y - 1][x],
+                _ => (b'A' + rng.below(plants) as u8) as char,
+            };
+        }
+    }
+    grid_to_string(&grid)
+}
+
+// Day 13: claw machines, some of them without any solution and some with collinear buttons.
+fn claw_machines(rng: &mut Rng, size: usize) -> String {
+    let mut machines = Vec::new();
+    for _ in 0..size {
+        let a = (rng.between(10, 99), rng.between(10, 99));
+        let b = if rng.chance(0.1) {
+            let k = rng.between(2, 3);
+            (a.0 * k, a.1 * k)
+        } else {
+            (rng.between(10, 99), rng.between(10, 99))
+        };
+        let (presses_a, presses_b) = (rng.between(0, 100), rng.between(0, 100));
+        let mut prize = (a.0 * presses_a + b.0 * presses_b, a.1 * presses_a + b.1 * presses_b);
+        if rng.chance(0.3) {
+            prize.0 += rng.between(1, 50);
+        }
+        machines.push(format!(
+            "Button A: X+{}, Y+{}\nButton B: X+{}, Y+{}\nPrize: X={}, Y={}\n",
+            a.0, a.1, b.0, b.1, prize.0, prize.1
+        ));
+    }
+    machines.join("\n")
+}
+
+// Day 14: robots in the puzzle's 101x103 space. The dimensions are inferred from the furthest
+// robot, so one of them always starts in the bottom-right corner.
+fn robots(rng: &mut Rng, size: usize) -> String {
+    let (width, height) = (101, 103);
+    let mut out = String::new();
+    for i in 0..size {
+        let position = if i == 0 {
+            (width - 1, height - 1)
+        } else {
+            (rng.between(0, width - 1), rng.between(0, height - 1))
+        };
+        let velocity = (rng.between(-99, 99), rng.between(-99, 99));
+        writeln!(out, "p={},{} v={},{}", position.0, position.1, velocity.0, velocity.1).unwrap();
+    }
+    out
+}
+
+// Day 15: a walled warehouse followed by `10 * size` moves.
+fn warehouse(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(3);
+    let mut grid: Vec<Vec<char>> = (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| {
+                    if x == 0 || y == 0 || x == size - 1 || y == size - 1 || rng.chance(0.1) {
+                        '#'
+                    } else if rng.chance(0.3) {
+                        'O'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let (x, y) = (rng.between(1, size as i64 - 2), rng.between(1, size as i64 - 2));
+    grid[y as usize][x as usize] = '@';
+
+    let mut out = grid_to_string(&grid) + "\n";
+    let moves: Vec<char> = (0..10 * size).map(|_| *rng.choose(&['^', 'v', '<', '>'])).collect();
+    for line in moves.chunks(70) {
+        writeln!(out, "{}", line.iter().collect::<String>()).unwrap();
+    }
+    out
+}
+
+// Day 16: a perfect maze carved by a randomized depth-first search, with a few extra walls
+// knocked down so that there is more than one way to the end.
+fn maze(rng: &mut Rng, size: usize) -> String {
+    let size = if size.is_multiple_of(2) { size + 1 } else { size }.max(5);
+    let mut grid = vec![vec!['#'; size]; size];
+
+    let start = (1, size - 2);
+    grid[start.1][start.0] = '.';
+    let mut stack = vec![start];
+    while let Some((x, y)) = stack.last().copied() {
+        let unvisited: Vec<_> = [(0, -2), (2, 0), (0, 2), (-2, 0)]
+            .iter()
+            .map(|(dx, dy)| (x as isize + dx, y as isize + dy))
+            .filter(|(nx, ny)| *nx > 0 && *ny > 0 && *nx < size as isize - 1 && *ny < size as isize - 1)
+            .map(|(nx, ny)| (nx as usize, ny as usize))
+            .filter(|(nx, ny)| grid[*ny][*nx] == '#')
+            .collect();
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let (nx, ny) = *rng.choose(&unvisited);
+        grid[(y + ny) / 2][(x + nx) / 2] = '.';
+        grid[ny][nx] = '.';
+        stack.push((nx, ny));
+    }
+
+    for _ in 0..size {
+        let (x, y) = (
+            rng.between(1, size as i64 - 2) as usize,
+            rng.between(1, size as i64 - 2) as usize,
+        );
+        // only walls between two corridors, never the pillars at even coordinates
+        if (x + y) % 2 == 1 {
+            grid[y][x] = '.';
+        }
+    }
+
+    grid[start.1][start.0] = 'S';
+    grid[1][size - 2] = 'E';
+    grid_to_string(&grid)
+}
@@ -14,7 +14,12 @@ mod day13;
 mod day14;
 mod day15;
 mod day16;
+mod difftest;
+mod generate;
+mod reference;
+mod run_all;
 
+use adventofcode::input::Input;
 use anyhow::Result;
 
 #[derive(Debug, clap::Args)]
@@ -73,27 +78,51 @@ pub enum Commands {
     /// Advent of Code 2024 - Day 16 - Reindeer Maze.
     #[command(visible_aliases = &["16"])]
     Day16(day16::Args),
+    /// Generate a random puzzle input for a day.
+    Generate(generate::Args),
+    /// Compare a day's solutions against slow reference solvers on generated inputs.
+    Difftest(difftest::Args),
+    /// Solve both parts of every day with a puzzle input.
+    RunAll(run_all::Args),
+    /// Check every input against the answers file next to it.
+    Verify(run_all::Args),
 }
 
 impl Args {
-    pub fn run(self, input: String) -> Result<()> {
+    // Line-oriented days stream their input, the others read it all at once in the `parse` span
+    // ahead of the `solve` one. Streaming days have `parse` spans of their own inside `solve`.
+    #[tracing::instrument(skip_all)]
+    pub fn run(self, input: Input) -> Result<()> {
         match self.command {
-            Commands::Day01(args) => args.run(input),
-            Commands::Day02(args) => args.run(input),
-            Commands::Day03(args) => args.run(input),
-            Commands::Day04(args) => args.run(input),
-            Commands::Day05(args) => args.run(input),
-            Commands::Day06(args) => args.run(input),
-            Commands::Day07(args) => args.run(input),
-            Commands::Day08(args) => args.run(input),
-            Commands::Day09(args) => args.run(input),
-            Commands::Day10(args) => args.run(input),
-            Commands::Day11(args) => args.run(input),
-            Commands::Day12(args) => args.run(input),
-            Commands::Day13(args) => args.run(input),
-            Commands::Day14(args) => args.run(input),
-            Commands::Day15(args) => args.run(input),
-            Commands::Day16(args) => args.run(input),
+            Commands::Day01(args) => solve(1, input, |input| args.run(input)),
+            Commands::Day02(args) => solve(2, input, |input| args.run(input)),
+            Commands::Day03(args) => solve(3, read_input(&input)?, |input| args.run(input)),
+            Commands::Day04(args) => solve(4, read_input(&input)?, |input| args.run(input)),
+            Commands::Day05(args) => solve(5, read_input(&input)?, |input| args.run(input)),
+            Commands::Day06(args) => solve(6, read_input(&input)?, |input| args.run(input)),
+            Commands::Day07(args) => solve(7, input, |input| args.run(input)),
+            Commands::Day08(args) => solve(8, read_input(&input)?, |input| args.run(input)),
+            Commands::Day09(args) => solve(9, read_input(&input)?, |input| args.run(input)),
+            Commands::Day10(args) => solve(10, read_input(&input)?, |input| args.run(input)),
+            Commands::Day11(args) => solve(11, read_input(&input)?, |input| args.run(input)),
+            Commands::Day12(args) => solve(12, read_input(&input)?, |input| args.run(input)),
+            Commands::Day13(args) => solve(13, input, |input| args.run(input)),
+            Commands::Day14(args) => solve(14, input, |input| args.run(input)),
+            Commands::Day15(args) => solve(15, read_input(&input)?, |input| args.run(input)),
+            Commands::Day16(args) => solve(16, read_input(&input)?, |input| args.run(input)),
+            Commands::Generate(args) => args.run(),
+            Commands::Difftest(args) => args.run(),
+            Commands::RunAll(args) => args.run_all(),
+            Commands::Verify(args) => args.verify(),
         }
     }
 }
+
+#[tracing::instrument(name = "parse", skip_all)]
+fn read_input(input: &Input) -> Result<String> {
+    input.read_to_string()
+}
+
+fn solve<I>(day: u32, input: I, run: impl FnOnce(I) -> Result<()>) -> Result<()> {
+    tracing::info_span!("solve", day).in_scope(|| run(input))
+}
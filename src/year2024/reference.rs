@@ -0,0 +1,787 @@
+// Slow but straightforward solutions, used as an oracle by `difftest`.
+//
+// They follow the puzzle statements as literally as possible and share no code with the actual
+// solutions. They are also strict about their input: an input they reject is considered invalid,
+// which keeps shrinking from wandering outside of the puzzle's rules.
+
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+use adventofcode::split2;
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
+
+pub type Solver = fn(&str) -> Result<u64>;
+
+type Grid = Vec<Vec<char>>;
+
+/// The reference solver of a part, if there is one.
+pub fn solver(day: u32, part: u32) -> Option<Solver> {
+    let solver: Solver = match (day, part) {
+        (1, 1) => day01_part1,
+        (1, 2) => day01_part2,
+        (2, 1) => day02_part1,
+        (2, 2) => day02_part2,
+        (3, 1) => day03_part1,
+        (3, 2) => day03_part2,
+        (4, 1) => day04_part1,
+        (4, 2) => day04_part2,
+        (5, 1) => day05_part1,
+        (5, 2) => day05_part2,
+        (6, 1) => day06_part1,
+        (6, 2) => day06_part2,
+        (7, 1) => day07_part1,
+        (7, 2) => day07_part2,
+        (8, 1) => day08_part1,
+        (8, 2) => day08_part2,
+        (9, 1) => day09_part1,
+        (9, 2) => day09_part2,
+        (10, 1) => day10_part1,
+        (10, 2) => day10_part2,
+        (11, 1) => day11_part1,
+        (11, 2) => day11_part2,
+        (12, 1) => day12_part1,
+        (12, 2) => day12_part2,
+        // part 2 adds 10^13 to the prizes, far out of reach of a brute force
+        (13, 1) => day13_part1,
+        // part 2 looks for a picture, there is no obviously correct way to do that
+        (14, 1) => day14_part1,
+        (15, 1) => day15_part1,
+        (15, 2) => day15_part2,
+        (16, 1) => day16_part1,
+        _ => return None,
+    };
+    Some(solver)
+}
+
+fn grid(input: &str) -> Result<Grid> {
+    let grid: Vec<Vec<char>> = input.lines().map(|l| l.chars().collect()).collect();
+    if grid.is_empty() || grid[0].is_empty() {
+        bail!("empty grid");
+    }
+    if grid.iter().any(|row| row.len() != grid[0].len()) {
+        bail!("rows have different lengths");
+    }
+    Ok(grid)
+}
+
+fn find(grid: &[Vec<char>], target: char) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, c) in row.iter().enumerate() {
+            if *c == target {
+                found.push((x, y));
+            }
+        }
+    }
+    found
+}
+
+fn step((x, y): (usize, usize), (dx, dy): (isize, isize), (width, height): (usize, usize)) -> Option<(usize, usize)> {
+    let (nx, ny) = (x as isize + dx, y as isize + dy);
+    (nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height).then_some((nx as usize, ny as usize))
+}
+
+const ORTHOGONAL: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+fn location_lists(input: &str) -> Result<(Vec<u64>, Vec<u64>)> {
+    input.lines().map(split2::<u64>).collect()
+}
+
+fn day01_part1(input: &str) -> Result<u64> {
+    let (mut left, mut right) = location_lists(input)?;
+    left.sort();
+    right.sort();
+    Ok(left.iter().zip(right.iter()).map(|(l, r)| l.abs_diff(*r)).sum())
+}
+
+fn day01_part2(input: &str) -> Result<u64> {
+    let (left, right) = location_lists(input)?;
+    Ok(left
+        .iter()
+        .map(|l| l * right.iter().filter(|r| *r == l).count() as u64)
+        .sum())
+}
+
+fn reports(input: &str) -> Result<Vec<Vec<i64>>> {
+    input
+        .lines()
+        .map(|l| {
+            l.split_whitespace()
+                .map(|v| v.parse().context("parsing level"))
+                .collect()
+        })
+        .collect()
+}
+
+fn safe(levels: &[i64]) -> bool {
+    let diffs: Vec<i64> = levels.windows(2).map(|w| w[1] - w[0]).collect();
+    diffs.iter().all(|d| (1..=3).contains(d)) || diffs.iter().all(|d| (-3..=-1).contains(d))
+}
+
+fn day02_part1(input: &str) -> Result<u64> {
+    Ok(reports(input)?.iter().filter(|r| safe(r)).count() as u64)
+}
+
+fn day02_part2(input: &str) -> Result<u64> {
+    let dampened_safe = |levels: &Vec<i64>| {
+        (0..levels.len()).any(|skip| {
+            let mut levels = levels.clone();
+            levels.remove(skip);
+            safe(&levels)
+        })
+    };
+    Ok(reports(input)?.iter().filter(|r| safe(r) || dampened_safe(r)).count() as u64)
+}
+
+fn day03_part1(input: &str) -> Result<u64> {
+    let re = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)").unwrap();
+    Ok(re
+        .captures_iter(input)
+        .map(|c| c[1].parse::<u64>().unwrap() * c[2].parse::<u64>().unwrap())
+        .sum())
+}
+
+fn day03_part2(input: &str) -> Result<u64> {
+    let re = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)|do\(\)|don't\(\)").unwrap();
+    let mut enabled = true;
+    let mut sum = 0;
+    for c in re.captures_iter(input) {
+        match &c[0] {
+            "do()" => enabled = true,
+            "don't()" => enabled = false,
+            _ if enabled => sum += c[1].parse::<u64>().unwrap() * c[2].parse::<u64>().unwrap(),
+            _ => {}
+        }
+    }
+    Ok(sum)
+}
+
+fn day04_part1(input: &str) -> Result<u64> {
+    let grid = grid(input)?;
+    let size = (grid[0].len(), grid.len());
+    let mut count = 0;
+    for (x, y) in (0..size.1).flat_map(|y| (0..size.0).map(move |x| (x, y))) {
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let mut pos = Some((x, y));
+                let mut word = String::new();
+                for _ in 0..4 {
+                    let Some((px, py)) = pos else { break };
+                    word.push(grid[py][px]);
+                    pos = step((px, py), (dx, dy), size);
+                }
+                if (dx, dy) != (0, 0) && word == "XMAS" {
+                    count += 1;
+                }
+            }
+        }
+    }
+    Ok(count)
+}
+
+fn day04_part2(input: &str) -> Result<u64> {
+    let grid = grid(input)?;
+    let mut count = 0;
+    for y in 1..grid.len().saturating_sub(1) {
+        for x in 1..grid[0].len() - 1 {
+            let diagonal1 = [grid[y - 1][x - 1], grid[y][x], grid[y + 1][x + 1]];
+            let diagonal2 = [grid[y - 1][x + 1], grid[y][x], grid[y + 1][x - 1]];
+            let mas = |d: [char; 3]| d == ['M', 'A', 'S'] || d == ['S', 'A', 'M'];
+            if mas(diagonal1) && mas(diagonal2) {
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+// Pairs of pages `(before, after)`.
+type Rules = HashSet<(u64, u64)>;
+
+// Every pair of pages of an update must be covered by a rule, otherwise the correct order is
+// ambiguous.
+fn print_queue(input: &str) -> Result<(Rules, Vec<Vec<u64>>)> {
+    let (rules, updates) = input.split_once("\n\n").ok_or(anyhow!("missing updates"))?;
+    let rules: Rules = rules
+        .lines()
+        .map(|l| {
+            let (before, after) = l.split_once('|').ok_or(anyhow!("invalid rule '{l}'"))?;
+            Ok((before.parse()?, after.parse()?))
+        })
+        .collect::<Result<_>>()?;
+    let updates: Vec<Vec<u64>> = updates
+        .lines()
+        .map(|l| l.split(',').map(|p| p.parse().context("parsing page")).collect())
+        .collect::<Result<_>>()?;
+    for update in updates.iter() {
+        if update.len() % 2 == 0 {
+            bail!("update {update:?} has no middle page");
+        }
+        for (i, a) in update.iter().enumerate() {
+            for b in update[i + 1..].iter() {
+                if rules.contains(&(*a, *b)) == rules.contains(&(*b, *a)) {
+                    bail!("pages {a} and {b} are not ordered by exactly one rule");
+                }
+            }
+        }
+    }
+    Ok((rules, updates))
+}
+
+fn ordered(rules: &Rules, update: &[u64]) -> bool {
+    (0..update.len()).all(|i| (i + 1..update.len()).all(|j| !rules.contains(&(update[j], update[i]))))
+}
+
+fn day05_part1(input: &str) -> Result<u64> {
+    let (rules, updates) = print_queue(input)?;
+    Ok(updates
+        .iter()
+        .filter(|u| ordered(&rules, u))
+        .map(|u| u[u.len() / 2])
+        .sum())
+}
+
+fn day05_part2(input: &str) -> Result<u64> {
+    let (rules, updates) = print_queue(input)?;
+    let mut sum = 0;
+    for update in updates.iter().filter(|u| !ordered(&rules, u)) {
+        // repeatedly take the page that must be printed before all the remaining ones
+        let mut remaining = update.clone();
+        let mut sorted = Vec::new();
+        while !remaining.is_empty() {
+            let first = (0..remaining.len())
+                .find(|i| {
+                    remaining
+                        .iter()
+                        .all(|p| *p == remaining[*i] || rules.contains(&(remaining[*i], *p)))
+                })
+                .ok_or(anyhow!("rules of update {update:?} contain a cycle"))?;
+            sorted.push(remaining.remove(first));
+        }
+        sum += sorted[sorted.len() / 2];
+    }
+    Ok(sum)
+}
+
+fn guard(grid: &[Vec<char>]) -> Result<((usize, usize), usize)> {
+    let mut guards = Vec::new();
+    for (dir, c) in ['^', '>', 'v', '<'].iter().enumerate() {
+        guards.extend(find(grid, *c).into_iter().map(|pos| (pos, dir)));
+    }
+    match guards[..] {
+        [guard] => Ok(guard),
+        _ => bail!("expected exactly one guard, found {}", guards.len()),
+    }
+}
+
+// Positions visited by the guard until it leaves the map, or None if it walks in a loop.
+fn patrol(grid: &[Vec<char>], (mut pos, mut dir): ((usize, usize), usize)) -> Option<HashSet<(usize, usize)>> {
+    let size = (grid[0].len(), grid.len());
+    let mut seen = HashSet::new();
+    while seen.insert((pos, dir)) {
+        let Some(next) = step(pos, ORTHOGONAL[dir], size) else {
+            return Some(seen.into_iter().map(|(pos, _)| pos).collect());
+        };
+        if grid[next.1][next.0] == '#' {
+            dir = (dir + 1) % 4;
+        } else {
+            pos = next;
+        }
+    }
+    None
+}
+
+fn day06_part1(input: &str) -> Result<u64> {
+    let grid = grid(input)?;
+    let guard = guard(&grid)?;
+    let visited = patrol(&grid, guard).ok_or(anyhow!("the guard never leaves"))?;
+    Ok(visited.len() as u64)
+}
+
+fn day06_part2(input: &str) -> Result<u64> {
+    let mut grid = grid(input)?;
+    let guard = guard(&grid)?;
+    patrol(&grid, guard).ok_or(anyhow!("the guard never leaves"))?;
+    let mut count = 0;
+    for (x, y) in find(&grid, '.') {
+        grid[y][x] = '#';
+        if patrol(&grid, guard).is_none() {
+            count += 1;
+        }
+        grid[y][x] = '.';
+    }
+    Ok(count)
+}
+
+fn equations(input: &str) -> Result<Vec<(u64, Vec<u64>)>> {
+    input
+        .lines()
+        .map(|l| {
+            let (test_value, nums) = l.split_once(": ").ok_or(anyhow!("invalid equation '{l}'"))?;
+            let nums: Vec<u64> = nums.split(' ').map(|n| n.parse()).collect::<Result<_, _>>()?;
+            if nums.is_empty() {
+                bail!("equation '{l}' has no numbers");
+            }
+            Ok((test_value.parse()?, nums))
+        })
+        .collect()
+}
+
+// Every result reachable by inserting operators between the numbers, evaluated left to right.
+fn results(nums: &[u64], concat: bool) -> Vec<u128> {
+    let mut results = vec![nums[0] as u128];
+    for n in nums[1..].iter().map(|n| *n as u128) {
+        let mut next = Vec::new();
+        for r in results {
+            next.push(r + n);
+            next.push(r * n);
+            if concat {
+                next.push(format!("{r}{n}").parse().unwrap());
+            }
+        }
+        results = next;
+    }
+    results
+}
+
+fn calibration(input: &str, concat: bool) -> Result<u64> {
+    Ok(equations(input)?
+        .iter()
+        .filter(|(test_value, nums)| results(nums, concat).contains(&(*test_value as u128)))
+        .map(|(test_value, _)| test_value)
+        .sum())
+}
+
+fn day07_part1(input: &str) -> Result<u64> {
+    calibration(input, false)
+}
+
+fn day07_part2(input: &str) -> Result<u64> {
+    calibration(input, true)
+}
+
+fn antenna_pairs(grid: &[Vec<char>]) -> Vec<((i64, i64), (i64, i64))> {
+    let mut antennas: HashMap<char, Vec<(i64, i64)>> = HashMap::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, c) in row.iter().enumerate().filter(|(_, c)| **c != '.') {
+            antennas.entry(*c).or_default().push((x as i64, y as i64));
+        }
+    }
+    let mut pairs = Vec::new();
+    for positions in antennas.values() {
+        for (i, a) in positions.iter().enumerate() {
+            for b in positions[i + 1..].iter() {
+                pairs.push((*a, *b));
+            }
+        }
+    }
+    pairs
+}
+
+fn day08_part1(input: &str) -> Result<u64> {
+    let grid = grid(input)?;
+    let (width, height) = (grid[0].len() as i64, grid.len() as i64);
+    let mut antinodes = HashSet::new();
+    for (a, b) in antenna_pairs(&grid) {
+        for (x, y) in [(2 * a.0 - b.0, 2 * a.1 - b.1), (2 * b.0 - a.0, 2 * b.1 - a.1)] {
+            if x >= 0 && y >= 0 && x < width && y < height {
+                antinodes.insert((x, y));
+            }
+        }
+    }
+    Ok(antinodes.len() as u64)
+}
+
+fn day08_part2(input: &str) -> Result<u64> {
+    let grid = grid(input)?;
+    let pairs = antenna_pairs(&grid);
+    let mut count = 0;
+    for y in 0..grid.len() as i64 {
+        for x in 0..grid[0].len() as i64 {
+            // in line with both antennas of a pair: the cross product is zero
+            if pairs
+                .iter()
+                .any(|(a, b)| (b.0 - a.0) * (y - a.1) == (b.1 - a.1) * (x - a.0))
+            {
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+// One entry per block, with the ID of the file it holds.
+fn blocks(input: &str) -> Result<Vec<Option<u64>>> {
+    let mut blocks = Vec::new();
+    for (i, c) in input.trim().chars().enumerate() {
+        let len = c.to_digit(10).ok_or(anyhow!("invalid digit '{c}'"))?;
+        let block = (i % 2 == 0).then_some(i as u64 / 2);
+        blocks.extend(std::iter::repeat_n(block, len as usize));
+    }
+    Ok(blocks)
+}
+
+fn checksum(blocks: &[Option<u64>]) -> u64 {
+    blocks.iter().enumerate().map(|(i, b)| i as u64 * b.unwrap_or(0)).sum()
+}
+
+fn day09_part1(input: &str) -> Result<u64> {
+    let mut blocks = blocks(input)?;
+    loop {
+        let free = blocks.iter().position(Option::is_none);
+        let last = blocks.iter().rposition(Option::is_some);
+        match (free, last) {
+            (Some(free), Some(last)) if free < last => blocks.swap(free, last),
+            _ => break,
+        }
+    }
+    Ok(checksum(&blocks))
+}
+
+fn day09_part2(input: &str) -> Result<u64> {
+    let mut blocks = blocks(input)?;
+    let max_id = blocks.iter().flatten().max().copied().unwrap_or(0);
+    for id in (0..=max_id).rev() {
+        let Some(start) = blocks.iter().position(|b| *b == Some(id)) else {
+            continue;
+        };
+        let len = blocks.iter().filter(|b| **b == Some(id)).count();
+        let free = (0..start).find(|i| blocks[*i..*i + len].iter().all(Option::is_none));
+        if let Some(free) = free {
+            for i in 0..len {
+                blocks.swap(free + i, start + i);
+            }
+        }
+    }
+    Ok(checksum(&blocks))
+}
+
+fn trails(grid: &[Vec<char>], pos: (usize, usize), peaks: &mut Vec<(usize, usize)>) {
+    let height = grid[pos.1][pos.0];
+    if height == '9' {
+        peaks.push(pos);
+        return;
+    }
+    for dir in ORTHOGONAL {
+        if let Some(next) = step(pos, dir, (grid[0].len(), grid.len())) {
+            if grid[next.1][next.0] as u32 == height as u32 + 1 {
+                trails(grid, next, peaks);
+            }
+        }
+    }
+}
+
+fn trail_map(input: &str) -> Result<Grid> {
+    let grid = grid(input)?;
+    if grid.iter().flatten().any(|c| !c.is_ascii_digit()) {
+        bail!("heights must be digits");
+    }
+    Ok(grid)
+}
+
+fn day10_part1(input: &str) -> Result<u64> {
+    let grid = trail_map(input)?;
+    let mut score = 0;
+    for trailhead in find(&grid, '0') {
+        let mut peaks = Vec::new();
+        trails(&grid, trailhead, &mut peaks);
+        score += peaks.iter().collect::<HashSet<_>>().len() as u64;
+    }
+    Ok(score)
+}
+
+fn day10_part2(input: &str) -> Result<u64> {
+    let grid = trail_map(input)?;
+    let mut rating = 0;
+    for trailhead in find(&grid, '0') {
+        let mut peaks = Vec::new();
+        trails(&grid, trailhead, &mut peaks);
+        rating += peaks.len() as u64;
+    }
+    Ok(rating)
+}
+
+fn blink(stone: u64) -> Vec<u64> {
+    let digits = stone.to_string();
+    if stone == 0 {
+        vec![1]
+    } else if digits.len().is_multiple_of(2) {
+        let (left, right) = digits.split_at(digits.len() / 2);
+        vec![left.parse().unwrap(), right.parse().unwrap()]
+    } else {
+        vec![stone * 2024]
+    }
+}
+
+fn stones(input: &str) -> Result<Vec<u64>> {
+    input
+        .split_whitespace()
+        .map(|s| s.parse().context("parsing stone"))
+        .collect()
+}
+
+fn day11_part1(input: &str) -> Result<u64> {
+    let mut stones = stones(input)?;
+    for _ in 0..25 {
+        stones = stones.into_iter().flat_map(blink).collect();
+    }
+    Ok(stones.len() as u64)
+}
+
+// Stones with the same number always evolve the same way, so only count them.
+fn day11_part2(input: &str) -> Result<u64> {
+    let mut counts: HashMap<u64, u64> = HashMap::new();
+    for stone in stones(input)? {
+        *counts.entry(stone).or_default() += 1;
+    }
+    for _ in 0..75 {
+        let mut next = HashMap::new();
+        for (stone, count) in counts {
+            for s in blink(stone) {
+                *next.entry(s).or_default() += count;
+            }
+        }
+        counts = next;
+    }
+    Ok(counts.values().sum())
+}
+
+// Price of every region, given a function of its cells.
+fn fence_price(input: &str, price: fn(&HashSet<(usize, usize)>) -> u64) -> Result<u64> {
+    let grid = grid(input)?;
+    let size = (grid[0].len(), grid.len());
+    let mut seen = HashSet::new();
+    let mut total = 0;
+    for start in (0..size.1).flat_map(|y| (0..size.0).map(move |x| (x, y))) {
+        if seen.contains(&start) {
+            continue;
+        }
+        let mut region = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(pos) = queue.pop_front() {
+            for next in ORTHOGONAL.iter().filter_map(|d| step(pos, *d, size)) {
+                if grid[next.1][next.0] == grid[start.1][start.0] && region.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        total += region.len() as u64 * price(&region);
+        seen.extend(region);
+    }
+    Ok(total)
+}
+
+// Fence segments as (cell, side of the cell).
+fn fences(region: &HashSet<(usize, usize)>) -> HashSet<((i64, i64), usize)> {
+    let mut fences = HashSet::new();
+    for (x, y) in region.iter().map(|(x, y)| (*x as i64, *y as i64)) {
+        for (dir, (dx, dy)) in ORTHOGONAL.iter().enumerate() {
+            let (nx, ny) = (x + *dx as i64, y + *dy as i64);
+            if nx < 0 || ny < 0 || !region.contains(&(nx as usize, ny as usize)) {
+                fences.insert(((x, y), dir));
+            }
+        }
+    }
+    fences
+}
+
+fn day12_part1(input: &str) -> Result<u64> {
+    fence_price(input, |region| fences(region).len() as u64)
+}
+
+// A side is a run of fence segments on the same side of adjacent cells: count the segments that
+// start a run, i.e. whose left (or upper) neighbour has no fence on that side.
+fn day12_part2(input: &str) -> Result<u64> {
+    fence_price(input, |region| {
+        let fences = fences(region);
+        fences
+            .iter()
+            .filter(|((x, y), dir)| {
+                let previous = if dir % 2 == 0 { (x - 1, *y) } else { (*x, y - 1) };
+                !fences.contains(&(previous, *dir))
+            })
+            .count() as u64
+    })
+}
+
+fn day13_part1(input: &str) -> Result<u64> {
+    let re = Regex::new(r"^Button A: X\+(\d+), Y\+(\d+)\nButton B: X\+(\d+), Y\+(\d+)\nPrize: X=(\d+), Y=(\d+)\n?$")
+        .unwrap();
+    let mut tokens = 0;
+    for machine in input.split("\n\n") {
+        let c = re
+            .captures(machine)
+            .ok_or(anyhow!("invalid claw machine '{machine}'"))?;
+        let [ax, ay, bx, by, px, py] = [1, 2, 3, 4, 5, 6].map(|i| c[i].parse::<u64>().unwrap());
+        if ax == 0 || bx == 0 {
+            bail!("buttons must move the claw along X");
+        }
+        let cheapest = (0..=px / ax)
+            .filter(|a| (px - a * ax) % bx == 0)
+            .map(|a| (a, (px - a * ax) / bx))
+            .filter(|(a, b)| a * ay + b * by == py)
+            .map(|(a, b)| 3 * a + b)
+            .min();
+        tokens += cheapest.unwrap_or(0);
+    }
+    Ok(tokens)
+}
+
+// The space is as big as the furthest robot, like in the puzzle inputs.
+fn day14_part1(input: &str) -> Result<u64> {
+    let re = Regex::new(r"^p=(\d+),(\d+) v=(-?\d+),(-?\d+)$").unwrap();
+    let mut robots = Vec::new();
+    for line in input.lines() {
+        let c = re.captures(line).ok_or(anyhow!("invalid robot '{line}'"))?;
+        let [px, py, vx, vy] = [1, 2, 3, 4].map(|i| c[i].parse::<i64>().unwrap());
+        robots.push(((px, py), (vx, vy)));
+    }
+    let width = robots
+        .iter()
+        .map(|((x, _), _)| x + 1)
+        .max()
+        .ok_or(anyhow!("no robots"))?;
+    let height = robots.iter().map(|((_, y), _)| y + 1).max().unwrap();
+
+    for _ in 0..100 {
+        for ((x, y), (vx, vy)) in robots.iter_mut() {
+            *x = (*x + *vx).rem_euclid(width);
+            *y = (*y + *vy).rem_euclid(height);
+        }
+    }
+
+    let mut quadrants = [0; 4];
+    for ((x, y), _) in robots {
+        // with an odd dimension, the robots exactly in the middle are in no quadrant
+        if (width % 2 == 1 && x == width / 2) || (height % 2 == 1 && y == height / 2) {
+            continue;
+        }
+        quadrants[(x < width / 2) as usize * 2 + (y < height / 2) as usize] += 1;
+    }
+    Ok(quadrants.iter().product())
+}
+
+fn warehouse(input: &str) -> Result<(Grid, Vec<(isize, isize)>)> {
+    let (map, moves) = input.split_once("\n\n").ok_or(anyhow!("missing moves"))?;
+    let grid = grid(map)?;
+    let (width, height) = (grid[0].len(), grid.len());
+    for (y, row) in grid.iter().enumerate() {
+        for (x, c) in row.iter().enumerate() {
+            let border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            if (border && *c != '#') || !"#.O@".contains(*c) {
+                bail!("invalid cell '{c}' at ({x}, {y})");
+            }
+        }
+    }
+    if find(&grid, '@').len() != 1 {
+        bail!("expected exactly one robot");
+    }
+    let moves = moves
+        .chars()
+        .filter(|c| *c != '\n')
+        .map(|c| match c {
+            '^' => Ok((0, -1)),
+            '>' => Ok((1, 0)),
+            'v' => Ok((0, 1)),
+            '<' => Ok((-1, 0)),
+            c => Err(anyhow!("invalid move '{c}'")),
+        })
+        .collect::<Result<_>>()?;
+    Ok((grid, moves))
+}
+
+// Whether whatever is at `pos` can be pushed by `dir`, along with everything it pushes.
+fn can_push(grid: &[Vec<char>], (x, y): (usize, usize), (dx, dy): (isize, isize)) -> bool {
+    let next = ((x as isize + dx) as usize, (y as isize + dy) as usize);
+    match grid[next.1][next.0] {
+        '#' => false,
+        '.' => true,
+        '[' if dy != 0 => can_push(grid, next, (dx, dy)) && can_push(grid, (next.0 + 1, next.1), (dx, dy)),
+        ']' if dy != 0 => can_push(grid, next, (dx, dy)) && can_push(grid, (next.0 - 1, next.1), (dx, dy)),
+        _ => can_push(grid, next, (dx, dy)),
+    }
+}
+
+fn push(grid: &mut [Vec<char>], (x, y): (usize, usize), (dx, dy): (isize, isize)) {
+    let next = ((x as isize + dx) as usize, (y as isize + dy) as usize);
+    match grid[next.1][next.0] {
+        '[' if dy != 0 => {
+            push(grid, next, (dx, dy));
+            push(grid, (next.0 + 1, next.1), (dx, dy));
+        }
+        ']' if dy != 0 => {
+            push(grid, next, (dx, dy));
+            push(grid, (next.0 - 1, next.1), (dx, dy));
+        }
+        '.' => {}
+        _ => push(grid, next, (dx, dy)),
+    }
+    grid[next.1][next.0] = grid[y][x];
+    grid[y][x] = '.';
+}
+
+fn gps_sum(mut grid: Grid, moves: &[(isize, isize)]) -> u64 {
+    let mut robot = find(&grid, '@')[0];
+    for dir in moves {
+        if can_push(&grid, robot, *dir) {
+            push(&mut grid, robot, *dir);
+            robot = ((robot.0 as isize + dir.0) as usize, (robot.1 as isize + dir.1) as usize);
+        }
+    }
+    ['O', '[']
+        .iter()
+        .flat_map(|c| find(&grid, *c))
+        .map(|(x, y)| (100 * y + x) as u64)
+        .sum()
+}
+
+fn day15_part1(input: &str) -> Result<u64> {
+    let (grid, moves) = warehouse(input)?;
+    Ok(gps_sum(grid, &moves))
+}
+
+fn day15_part2(input: &str) -> Result<u64> {
+    let (grid, moves) = warehouse(input)?;
+    let wide = grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .flat_map(|c| match c {
+                    'O' => ['[', ']'],
+                    '@' => ['@', '.'],
+                    c => [*c, *c],
+                })
+                .collect()
+        })
+        .collect();
+    Ok(gps_sum(wide, &moves))
+}
+
+// Dijkstra over (position, direction) states: turning is a move of its own.
+fn day16_part1(input: &str) -> Result<u64> {
+    let grid = grid(input)?;
+    let size = (grid[0].len(), grid.len());
+    let (starts, ends) = (find(&grid, 'S'), find(&grid, 'E'));
+    let ([start], [end]) = (&starts[..], &ends[..]) else {
+        bail!("expected exactly one start and one end");
+    };
+
+    let mut best = HashMap::new();
+    let mut queue = BinaryHeap::from([std::cmp::Reverse((0u64, *start, 1usize))]);
+    while let Some(std::cmp::Reverse((score, pos, dir))) = queue.pop() {
+        if pos == *end {
+            return Ok(score);
+        }
+        if best.get(&(pos, dir)).is_some_and(|b| *b <= score) {
+            continue;
+        }
+        best.insert((pos, dir), score);
+        if let Some(next) = step(pos, ORTHOGONAL[dir], size).filter(|(x, y)| grid[*y][*x] != '#') {
+            queue.push(std::cmp::Reverse((score + 1, next, dir)));
+        }
+        for turn in [1, 3] {
+            queue.push(std::cmp::Reverse((score + 1000, pos, (dir + turn) % 4)));
+        }
+    }
+    Err(anyhow!("the end can't be reached"))
+}
@@ -0,0 +1,449 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use adventofcode::alloc::{format_bytes, AllocStats};
+use anyhow::{anyhow, Context, Result};
+use serde_json::json;
+
+use super::difftest::commands;
+
+const DAYS: u32 = 16;
+
+/// Options shared by `run-all` and `verify`.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Directory holding the `2024/dayNN` input directories.
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+
+    /// Number of solutions running at the same time. Results are still printed in day order, but
+    /// timings are only accurate with 1, as concurrent solutions compete for the cores.
+    #[arg(long, short, default_value_t = 1)]
+    jobs: usize,
+
+    /// Stop solutions running for longer than this, which are then reported as TIMEOUT (e.g.
+    /// `10s`).
+    #[arg(long, value_parser = crate::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Logging of the solutions, as for the top-level `-v`. Their logs are printed after their
+    /// result.
+    #[arg(long, short = 'v', action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Print one JSON object per solution instead, as soon as it is done, with its time in
+    /// milliseconds and its allocations when counted. The summary goes to stderr.
+    #[arg(long)]
+    json: bool,
+
+    /// Run every solution a second time on a single thread, and report how much faster it is on
+    /// all the cores. Only builds with the `parallel` feature use more than one.
+    #[arg(long)]
+    speedup: bool,
+}
+
+// One solution to run on one input, with the answer expected when verifying.
+#[derive(Debug)]
+struct Job {
+    day: u32,
+    command: String,
+    input: PathBuf,
+    expected: Option<String>,
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Answer(String),
+    Failed(String),
+    TimedOut,
+}
+
+// What `run_jobs` reports once every job is done.
+#[derive(Debug)]
+struct Totals {
+    failures: usize,
+    elapsed: Duration,
+    // total time on a single thread and on all cores, of the solutions that succeeded on both
+    compared: (Duration, Duration),
+}
+
+#[derive(Debug)]
+struct Run {
+    outcome: Outcome,
+    elapsed: Duration,
+    // time of the same solution on a single thread, with `--speedup`, when it succeeded there too
+    serial: Option<Duration>,
+    // only counted when built with the `alloc-stats` feature
+    allocations: Option<AllocStats>,
+    // everything the solution logged, printed in one go so that concurrent solutions don't
+    // interleave
+    logs: String,
+}
+
+impl Args {
+    /// Solve both parts of every day with a puzzle input.
+    pub fn run_all(self) -> Result<()> {
+        let mut jobs = Vec::new();
+        for day in 1..=DAYS {
+            let input = self.day_dir(day).join("input.txt");
+            if !input.exists() {
+                continue;
+            }
+            for command in [1, 2].into_iter().flat_map(|part| commands(day, part)) {
+                jobs.push(Job {
+                    day,
+                    command: command.to_string(),
+                    input: input.clone(),
+                    expected: None,
+                });
+            }
+        }
+
+        if !self.json {
+            println!("{:<6} {:<17} {} answer", "day", "command", self.header());
+        }
+        let totals = self.run_jobs(&jobs, |job, run| {
+            let (status, answer) = match &run.outcome {
+                Outcome::Answer(answer) if answer.is_empty() => ("ok", String::from("no answer")),
+                Outcome::Answer(answer) => ("ok", answer.clone()),
+                Outcome::Failed(reason) => ("failed", format!("FAIL {reason}")),
+                Outcome::TimedOut => ("timeout", String::from("TIMEOUT")),
+            };
+            if self.json {
+                println!("{}", record(job, run, status));
+            } else {
+                println!("day {:02} {:<17} {} {answer}", job.day, job.command, self.columns(run));
+            }
+            status != "ok"
+        });
+        let failures = totals.failures;
+        self.summary(
+            format!("{} solutions in {:.1?}, {failures} failed", jobs.len(), totals.elapsed),
+            &totals,
+        );
+        match failures {
+            0 => Ok(()),
+            _ => Err(anyhow!("{failures} solution(s) failed")),
+        }
+    }
+
+    /// Check every input that has an answers file next to it, e.g. `input_example.txt` with
+    /// `answers_example.txt` listing `<subcommand>: <answer>` lines.
+    pub fn verify(self) -> Result<()> {
+        let mut jobs = Vec::new();
+        for day in 1..=DAYS {
+            let dir = self.day_dir(day);
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut inputs: Vec<PathBuf> = entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.file_name().is_some_and(|n| is_input(&n.to_string_lossy())))
+                .collect();
+            inputs.sort();
+            for input in inputs {
+                for (command, expected) in answers(&input)? {
+                    jobs.push(Job {
+                        day,
+                        command,
+                        input: input.clone(),
+                        expected: Some(expected),
+                    });
+                }
+            }
+        }
+
+        if !self.json {
+            println!(
+                "{:<7} {:<6} {:<20} {:<17} {} detail",
+                "status",
+                "day",
+                "input",
+                "command",
+                self.header()
+            );
+        }
+        let totals = self.run_jobs(&jobs, |job, run| {
+            let expected = job.expected.as_deref().unwrap_or_default();
+            let (status, detail) = match &run.outcome {
+                Outcome::Answer(answer) if answer == expected => ("ok", answer.clone()),
+                Outcome::Answer(answer) => ("wrong", format!("{answer}, expected {expected}")),
+                Outcome::Failed(reason) => ("failed", reason.clone()),
+                Outcome::TimedOut => ("timeout", format!("expected {expected}")),
+            };
+            if self.json {
+                println!("{}", record(job, run, status));
+            } else {
+                let name = job.input.file_name().unwrap_or_default().to_string_lossy();
+                let status = match status {
+                    "ok" => "ok",
+                    "timeout" => "TIMEOUT",
+                    _ => "FAIL",
+                };
+                println!(
+                    "{status:<7} day {:02} {name:<20} {:<17} {} {detail}",
+                    job.day,
+                    job.command,
+                    self.columns(run)
+                );
+            }
+            status != "ok"
+        });
+        let failures = totals.failures;
+        self.summary(
+            format!(
+                "{} answers checked in {:.1?}, {failures} wrong",
+                jobs.len(),
+                totals.elapsed
+            ),
+            &totals,
+        );
+        match failures {
+            0 => Ok(()),
+            _ => Err(anyhow!("{failures} answer(s) wrong")),
+        }
+    }
+
+    // Kept out of the JSON objects on stdout.
+    fn summary(&self, mut summary: String, totals: &Totals) {
+        if self.speedup && !totals.compared.1.is_zero() {
+            let (serial, parallel) = totals.compared;
+            summary += &format!(
+                ", {:.2}x faster on all cores ({serial:.1?} on a single thread, {parallel:.1?} on all)",
+                speedup(serial, parallel)
+            );
+        }
+        if self.json {
+            eprintln!("{summary}");
+        } else {
+            println!("{summary}");
+        }
+    }
+
+    // Names of the columns printed by `columns`.
+    fn header(&self) -> String {
+        let mut header = format!("{:>9}", "time");
+        if self.speedup {
+            header += &format!(" {:>9} {:>7}", "1 thread", "speedup");
+        }
+        if cfg!(feature = "alloc-stats") {
+            header += &format!(" {:>11} {:>9} {:>9}", "allocations", "allocated", "peak");
+        }
+        header
+    }
+
+    // The time of a run, then its time on a single thread and the speedup with `--speedup`, then
+    // its allocations when the solutions count them (they are this same binary, so they do
+    // exactly when it does).
+    fn columns(&self, run: &Run) -> String {
+        let mut columns = format!("{:>9}", format!("{:.1?}", run.elapsed));
+        if self.speedup {
+            let (serial, speedup) = match run.serial {
+                Some(serial) => (format!("{serial:.1?}"), format!("{:.2}x", speedup(serial, run.elapsed))),
+                None => ("-".into(), "-".into()),
+            };
+            columns += &format!(" {serial:>9} {speedup:>7}");
+        }
+        if cfg!(feature = "alloc-stats") {
+            let (allocations, allocated, peak) = match run.allocations {
+                Some(stats) => (
+                    stats.allocations.to_string(),
+                    format_bytes(stats.allocated),
+                    format_bytes(stats.peak),
+                ),
+                None => ("-".into(), "-".into(), "-".into()),
+            };
+            columns += &format!(" {allocations:>11} {allocated:>9} {peak:>9}");
+        }
+        columns
+    }
+
+    fn day_dir(&self, day: u32) -> PathBuf {
+        self.dir.join("2024").join(format!("day{day:02}"))
+    }
+
+    // Jobs are handed out in order to `jobs` threads. Their results are reported in the same
+    // order, each as soon as every job before it is done. `report` returns whether the job
+    // failed.
+    fn run_jobs(&self, jobs: &[Job], mut report: impl FnMut(&Job, &Run) -> bool) -> Totals {
+        let start = Instant::now();
+        let next = AtomicUsize::new(0);
+        let (tx, rx) = mpsc::channel();
+        let mut failures = 0;
+        let mut compared = (Duration::ZERO, Duration::ZERO);
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.max(1) {
+                let tx = tx.clone();
+                let next = &next;
+                scope.spawn(move || loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(job) = jobs.get(i) else {
+                        break;
+                    };
+                    let mut run = self.run(job, None);
+                    if self.speedup {
+                        let serial = self.run(job, Some(1));
+                        run.serial = matches!(serial.outcome, Outcome::Answer(_)).then_some(serial.elapsed);
+                    }
+                    if tx.send((i, run)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(tx);
+
+            let mut done = BTreeMap::new();
+            let mut reported = 0;
+            for (i, run) in rx {
+                done.insert(i, run);
+                while let Some(run) = done.remove(&reported) {
+                    if report(&jobs[reported], &run) {
+                        failures += 1;
+                    } else if let Some(serial) = run.serial {
+                        compared.0 += serial;
+                        compared.1 += run.elapsed;
+                    }
+                    _ = std::io::stdout().flush();
+                    if !run.logs.is_empty() {
+                        eprint!("{}", run.logs);
+                    }
+                    reported += 1;
+                }
+            }
+        });
+        Totals {
+            failures,
+            elapsed: start.elapsed(),
+            compared,
+        }
+    }
+
+    // Solutions run as a child process of this binary, so that a panic only fails their own
+    // result and their logs can be captured. The answer is the first line printed.
+    fn run(&self, job: &Job, threads: Option<usize>) -> Run {
+        let mut solution = Command::new(std::env::current_exe().expect("finding the current executable"));
+        solution.arg("--input").arg(&job.input);
+        if let Some(timeout) = self.timeout {
+            solution.arg("--timeout").arg(format!("{}ms", timeout.as_millis()));
+        }
+        if self.verbose > 0 {
+            solution.arg(format!("-{}", "v".repeat(self.verbose as usize)));
+        }
+        if let Some(threads) = threads {
+            solution.arg("--threads").arg(threads.to_string());
+        }
+        solution.args(["2024", &format!("day{:02}", job.day), &job.command]);
+
+        let start = Instant::now();
+        let output = match solution.output() {
+            Ok(output) => output,
+            Err(err) => {
+                return Run {
+                    outcome: Outcome::Failed(format!("running solution: {err}")),
+                    elapsed: start.elapsed(),
+                    serial: None,
+                    allocations: None,
+                    logs: String::new(),
+                }
+            }
+        };
+        let elapsed = start.elapsed();
+        // the allocation counters are reported on a line of their own, see `AllocStats`
+        let mut allocations = None;
+        let logs: String = String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .filter(|line| match line.parse() {
+                Ok(stats) => {
+                    allocations = Some(stats);
+                    false
+                }
+                Err(()) => true,
+            })
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let outcome = if output.status.code() == Some(crate::TIMEOUT_STATUS) {
+            Outcome::TimedOut
+        } else if !output.status.success() {
+            let reason = logs
+                .lines()
+                .find(|l| l.contains("panicked at") || l.starts_with("Error"))
+                .unwrap_or_default();
+            Outcome::Failed(format!("{}: {reason}", output.status))
+        } else {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            Outcome::Answer(stdout.lines().next().unwrap_or_default().trim().to_string())
+        };
+        Run {
+            outcome,
+            elapsed,
+            serial: None,
+            allocations,
+            logs,
+        }
+    }
+}
+
+fn record(job: &Job, run: &Run, status: &str) -> serde_json::Value {
+    let mut record = json!({
+        "status": status,
+        "day": job.day,
+        "command": job.command,
+        "input": job.input,
+        "elapsed_ms": run.elapsed.as_secs_f64() * 1000.0,
+    });
+    match &run.outcome {
+        Outcome::Answer(answer) => record["answer"] = answer.as_str().into(),
+        Outcome::Failed(reason) => record["error"] = reason.as_str().into(),
+        Outcome::TimedOut => {}
+    }
+    if let Some(expected) = &job.expected {
+        record["expected"] = expected.as_str().into();
+    }
+    if let Some(serial) = run.serial {
+        record["serial_ms"] = (serial.as_secs_f64() * 1000.0).into();
+        record["speedup"] = speedup(serial, run.elapsed).into();
+    }
+    if let Some(stats) = run.allocations {
+        record["allocations"] = stats.allocations.into();
+        record["allocated"] = stats.allocated.into();
+        record["peak"] = stats.peak.into();
+    }
+    record
+}
+
+// How many times faster `parallel` is than `serial`.
+fn speedup(serial: Duration, parallel: Duration) -> f64 {
+    serial.as_secs_f64() / parallel.as_secs_f64()
+}
+
+fn is_input(name: &str) -> bool {
+    name.starts_with("input") && name.ends_with(".txt")
+}
+
+// Inputs without an answers file have nothing to be checked against.
+fn answers(input: &Path) -> Result<Vec<(String, String)>> {
+    let name = input.file_name().unwrap_or_default().to_string_lossy();
+    let path = input.with_file_name(name.replacen("input", "answers", 1));
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let answers = fs::read_to_string(&path).with_context(|| format!("reading '{}'", path.display()))?;
+    answers
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            let (command, answer) = l
+                .split_once(':')
+                .ok_or_else(|| anyhow!("{}: invalid line '{l}'", path.display()))?;
+            Ok((command.trim().to_string(), answer.trim().to_string()))
+        })
+        .collect()
+}
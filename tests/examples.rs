@@ -0,0 +1,106 @@
+//! Runs every solution against the examples committed next to the puzzle inputs.
+//!
+//! Each `<year>/dayNN/input_example*.txt` is paired with an `answers_example*.txt` file listing the
+//! expected answers, one `<subcommand>: <answer>` per line. Adding both files is enough for a new
+//! day or example to be covered.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn examples() -> Vec<(String, String, PathBuf)> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut examples = Vec::new();
+    for year in fs::read_dir(root).unwrap().flatten() {
+        let year_name = year.file_name().to_string_lossy().to_string();
+        if year_name.len() != 4 || !year_name.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        for day in fs::read_dir(year.path()).unwrap().flatten() {
+            let day_name = day.file_name().to_string_lossy().to_string();
+            for file in fs::read_dir(day.path()).unwrap().flatten() {
+                let name = file.file_name().to_string_lossy().to_string();
+                if name.starts_with("input_example") && name.ends_with(".txt") {
+                    examples.push((year_name.clone(), day_name.clone(), file.path()));
+                }
+            }
+        }
+    }
+    examples.sort();
+    examples
+}
+
+fn answers(input: &Path) -> Vec<(String, String)> {
+    let name = input
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .replacen("input_", "answers_", 1);
+    let path = input.with_file_name(name);
+    let answers = fs::read_to_string(&path).unwrap_or_else(|err| panic!("reading {}: {err}", path.display()));
+    answers
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            let (command, answer) = l
+                .split_once(':')
+                .unwrap_or_else(|| panic!("{}: invalid line '{l}'", path.display()));
+            (command.trim().to_string(), answer.trim().to_string())
+        })
+        .collect()
+}
+
+fn run(year: &str, day: &str, command: &str, input: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_adventofcode"))
+        .arg("--input")
+        .arg(input)
+        .args([year, day, command])
+        .output()
+        .unwrap();
+    if !output.status.success() {
+        return format!(
+            "failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().next().unwrap_or_default().trim().to_string()
+}
+
+#[test]
+fn examples_match_expected_answers() {
+    let examples = examples();
+    assert!(!examples.is_empty(), "no example found");
+
+    let mut failures = Vec::new();
+    for (year, day, input) in examples.iter() {
+        for (command, expected) in answers(input) {
+            let actual = run(year, day, &command, input);
+            if actual != expected {
+                failures.push(format!(
+                    "{} {command}: expected {expected}, got {actual}",
+                    input.display()
+                ));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn verify_reports_in_day_order_on_several_threads() {
+    let output = Command::new(env!("CARGO_BIN_EXE_adventofcode"))
+        .args(["2024", "verify", "--jobs", "4", "--dir", env!("CARGO_MANIFEST_DIR")])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+
+    let days: Vec<&str> = stdout
+        .lines()
+        .filter_map(|l| l.split_once(" day ").map(|(_, rest)| &rest[..2]))
+        .collect();
+    assert!(!days.is_empty(), "{stdout}");
+    assert!(days.is_sorted(), "{stdout}");
+}